@@ -0,0 +1,272 @@
+use gorst_common::{
+    board::{Board, IntersectionState},
+    coordinates,
+    gamecommand::GameCommand,
+    gamestate::{GameState, GameStatus},
+    gtp::{self, GtpCommand},
+    mcts::{MctsPlayer, SearchBudget},
+    player::{Player, RandomPlayer},
+    scoring,
+};
+
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+
+extern crate gorst_common;
+
+/// Every command we support, as listed by list_commands
+const COMMANDS: [&str; 14] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "showboard",
+    "final_score",
+];
+
+/// The board size we start with, until the controller sets one
+const DEFAULT_SIZE: usize = 19;
+
+/// The largest board GTP vertices can describe
+const MAX_SIZE: usize = 25;
+
+/// How many playouts genmove searches, unless given with --playouts
+const DEFAULT_PLAYOUTS: usize = 10000;
+
+fn main() {
+    let mut playouts = DEFAULT_PLAYOUTS;
+    let mut random = false;
+
+    // Optionally pick how long genmove searches with --playouts n, or play random moves with --random
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--random" => random = true,
+            "--playouts" => match arguments.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n > 0 => playouts = n,
+                _ => {
+                    eprintln!("--playouts needs to be a positive number");
+                    std::process::exit(1);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument: {}", argument);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let player: Box<dyn Player> = if random {
+        Box::new(RandomPlayer::new())
+    } else {
+        let mut player = MctsPlayer::new(SearchBudget::Playouts(playouts));
+        player.threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Box::new(player)
+    };
+
+    let mut engine = Engine {
+        gamestate: GameState::with_board(Board::new(DEFAULT_SIZE)),
+        player,
+    };
+
+    let mut stdout = std::io::stdout().lock();
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        let Some(command) = GtpCommand::parse(&line) else {
+            continue;
+        };
+
+        let response = engine.execute(&command);
+
+        if write!(stdout, "{}", gtp::format_response(command.id, response)).is_err()
+            || stdout.flush().is_err()
+        {
+            break;
+        }
+
+        if command.name == "quit" {
+            break;
+        }
+    }
+}
+
+/// Answers GTP commands from a controller, keeping track of the game
+struct Engine {
+    gamestate: GameState,
+    /// Picks the moves we generate
+    player: Box<dyn Player>,
+}
+
+impl Engine {
+    /// Executes a command, returning the response or the error message
+    fn execute(&mut self, command: &GtpCommand) -> Result<String, String> {
+        match command.name.as_str() {
+            "protocol_version" => Ok(String::from("2")),
+            "name" => Ok(String::from("gorst")),
+            "version" => Ok(String::from(env!("CARGO_PKG_VERSION"))),
+            "known_command" => {
+                let name = argument(command, 0)?;
+                Ok(COMMANDS.contains(&name).to_string())
+            }
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => {
+                let size = argument(command, 0)?
+                    .parse::<usize>()
+                    .map_err(|_| String::from("syntax error"))?;
+
+                if !(1..=MAX_SIZE).contains(&size) {
+                    return Err(String::from("unacceptable size"));
+                }
+
+                self.new_game(size);
+                Ok(String::new())
+            }
+            "clear_board" => {
+                self.new_game(self.gamestate.current().board.size());
+                Ok(String::new())
+            }
+            "komi" => {
+                self.gamestate.ruleset.komi = argument(command, 0)?
+                    .parse::<f32>()
+                    .map_err(|_| String::from("syntax error"))?;
+                Ok(String::new())
+            }
+            "play" => {
+                let player = parse_color(argument(command, 0)?)?;
+                let size = self.gamestate.current().board.size();
+
+                let command = match coordinates::parse_vertex(argument(command, 1)?, size)
+                    .map_err(|_| String::from("syntax error"))?
+                {
+                    Some((x, y)) => GameCommand::Set(x, y, player),
+                    None => GameCommand::Pass,
+                };
+
+                self.gamestate = self
+                    .gamestate
+                    .apply_command_as(player, command)
+                    .map_err(|_| String::from("illegal move"))?;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let player = parse_color(argument(command, 0)?)?;
+
+                // Like play, genmove can ask for either colour, even after a pass
+                let mut gamestate = self.gamestate.clone();
+                if gamestate.status == GameStatus::Passed {
+                    gamestate = gamestate
+                        .apply_command(GameCommand::Resume)
+                        .map_err(|e| e.to_string())?;
+                }
+                gamestate.to_move = player;
+
+                let command = self.player.genmove(&gamestate).map_err(|e| e.to_string())?;
+
+                self.gamestate = gamestate
+                    .apply_command(command)
+                    .map_err(|e| e.to_string())?;
+
+                let size = self.gamestate.current().board.size();
+
+                Ok(match command {
+                    GameCommand::Set(x, y, _) => coordinates::format_vertex(Some((x, y)), size),
+                    GameCommand::Resign(_) => String::from("resign"),
+                    _ => coordinates::format_vertex(None, size),
+                })
+            }
+            "undo" => {
+                self.gamestate = self
+                    .gamestate
+                    .apply_command(GameCommand::Undo)
+                    .map_err(|_| String::from("cannot undo"))?;
+                Ok(String::new())
+            }
+            "showboard" => Ok(self.board_diagram()),
+            "final_score" => {
+                let report = scoring::score(
+                    self.gamestate.current(),
+                    &HashSet::new(),
+                    &self.gamestate.ruleset,
+                );
+
+                Ok(match report.winner() {
+                    Some(IntersectionState::White) => format!("W+{}", report.margin()),
+                    Some(_) => format!("B+{}", report.margin()),
+                    None => String::from("0"),
+                })
+            }
+            _ => Err(String::from("unknown command")),
+        }
+    }
+
+    /// Starts a new game on an empty board, keeping the rules and komi
+    fn new_game(&mut self, size: usize) {
+        let ruleset = self.gamestate.ruleset;
+
+        self.gamestate = GameState::with_board(Board::new(size));
+        self.gamestate.ruleset = ruleset;
+    }
+
+    /// Draws the board with X for black and O for white, labelled with GTP vertices
+    fn board_diagram(&self) -> String {
+        let boardstate = self.gamestate.current();
+        let size = boardstate.board.size();
+
+        let columns: String = (0..size)
+            .map(|x| format!(" {}", coordinates::column_name(x)))
+            .collect();
+
+        // Start with a line break, so the board starts on its own line after the "="
+        let mut diagram = format!("\n   {}\n", columns);
+
+        for y in 0..size {
+            diagram.push_str(&format!("{:>2} ", size - y));
+
+            for x in 0..size {
+                diagram.push(' ');
+                diagram.push(match boardstate.board.get_intersection(x, y) {
+                    Some(IntersectionState::Black) => 'X',
+                    Some(IntersectionState::White) => 'O',
+                    _ => '.',
+                });
+            }
+
+            diagram.push_str(&format!(" {}\n", size - y));
+        }
+
+        diagram.push_str(&format!("   {}\n", columns));
+        diagram.push_str(&format!(
+            "Black prisoners: {}, white prisoners: {}",
+            boardstate.black_prisoners, boardstate.white_prisoners
+        ));
+
+        diagram
+    }
+}
+
+/// Returns an argument of a command, or a syntax error if it's missing
+fn argument(command: &GtpCommand, index: usize) -> Result<&str, String> {
+    command
+        .arguments
+        .get(index)
+        .map(String::as_str)
+        .ok_or(String::from("syntax error"))
+}
+
+/// Parses a colour argument, or returns a syntax error
+fn parse_color(color: &str) -> Result<IntersectionState, String> {
+    gtp::parse_color(color).ok_or(String::from("syntax error"))
+}
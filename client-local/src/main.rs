@@ -1,131 +1,555 @@
 use gorst_common::{
-    board::Board,
-    errors,
+    board::{Board, IntersectionState},
+    command::{Command, COMMANDS},
+    coordinates, errors,
     gamecommand::GameCommand,
-    gamestate::{BoardState, GameState},
-    rendering::render_board,
-    ASCII,
+    gamestate::{GameState, GameStatus},
+    gtpengine::GtpEngine,
+    marking::DeadStoneMarking,
+    mcts::{MctsPlayer, SearchBudget},
+    player::{Player, RandomPlayer},
+    rendering::{self, AnsiRenderer, Overlay, Renderer},
+    ruleset::ScoringRule,
+    scoring::{self, ScoreReport},
+    sgf,
 };
 
+use std::collections::HashSet;
+
+mod tui;
+
 extern crate gorst_common;
 
+/// How many playouts the built-in bot searches per move, unless given with --playouts
+const DEFAULT_PLAYOUTS: usize = 10000;
+
 fn main() {
-    let mut board_size = 9;
+    let mut board_size = (9, 9);
+    let mut path = None;
+    let mut engine_command = None;
+    let mut engine_color = IntersectionState::White;
+    let mut bot_color = None;
+    let mut random_bot = false;
+    let mut playouts = DEFAULT_PLAYOUTS;
+    let mut full_screen = false;
+    let mut renderer: Box<dyn Renderer> = Box::new(AnsiRenderer);
 
-    // Optionally provide the board size as the first argument
-    if let Some(size_str) = std::env::args().nth(1) {
-        if let Ok(size_as_num) = size_str.parse::<usize>() {
-            board_size = size_as_num;
+    // Optionally provide the board size, like 19 or 13x9, or an SGF file to load,
+    // a GTP engine to play against with --engine "{command}" [--engine-color b|w],
+    // or let the built-in bot play one side with --vs-bot b|w [--playouts n] [--random];
+    // --tui plays in a full-screen interface instead of typing commands,
+    // and --renderer ansi|unicode|ascii chooses how the board is drawn
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--engine" => engine_command = arguments.next(),
+            "--engine-color" => engine_color = parse_color_argument(&argument, arguments.next()),
+            "--vs-bot" => bot_color = Some(parse_color_argument(&argument, arguments.next())),
+            "--random" => random_bot = true,
+            "--tui" => full_screen = true,
+            "--renderer" => {
+                renderer = match arguments
+                    .next()
+                    .and_then(|name| rendering::renderer_from_name(&name))
+                {
+                    Some(renderer) => renderer,
+                    None => {
+                        println!(
+                            "--renderer needs to be one of {}",
+                            rendering::RENDERER_NAMES.join(", ")
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--playouts" => {
+                playouts = match arguments.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(playouts) if playouts > 0 => playouts,
+                    _ => {
+                        println!("--playouts needs to be a positive number");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            _ => match parse_board_size(&argument) {
+                Some((0, _) | (_, 0)) => {
+                    println!("The board needs to be at least 1x1");
+                    std::process::exit(1);
+                }
+                Some(size) => board_size = size,
+                None => path = Some(argument),
+            },
         }
     }
 
-    let mut gamestate = GameState {
-        history: vec![BoardState {
-            board: Board::new(board_size),
-            ..Default::default()
-        }],
+    let mut session = Session {
+        gamestate: GameState::with_board(Board::new_rect(board_size.0, board_size.1)),
+        edit_mode: false,
+        marking: None,
+        opponent: None,
+        opponent_color: engine_color,
+        renderer,
     };
 
+    if let Some(path) = path {
+        if let Err(e) = load_game(&path, &mut session) {
+            println!("{}", e);
+        }
+    }
+
+    if let Some(engine_command) = engine_command {
+        let command_line: Vec<String> = engine_command
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
+        match GtpEngine::spawn(&command_line) {
+            Ok(engine) => set_opponent(&mut session, Box::new(engine), engine_color),
+            Err(e) => println!("{}", e),
+        }
+    } else if let Some(bot_color) = bot_color {
+        let bot: Box<dyn Player> = if random_bot {
+            Box::new(RandomPlayer::new())
+        } else {
+            let mut bot = MctsPlayer::new(SearchBudget::Playouts(playouts));
+            bot.threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            Box::new(bot)
+        };
+
+        set_opponent(&mut session, bot, bot_color);
+    }
+
+    if full_screen {
+        if let Err(e) = tui::run(&mut session) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     loop {
-        let latest_boardstate = gamestate.history.get(gamestate.history.len() - 1).unwrap();
-        render_board(&latest_boardstate.board);
+        if let Err(e) = play_opponent_move(&mut session) {
+            println!("{}", e);
+            println!("Stopped playing against {}", session.opponent_name());
+            session.opponent = None;
+        }
+
+        let gamestate = &session.gamestate;
+        let latest_boardstate = gamestate.current();
+
+        let overlay = Overlay {
+            marked: session
+                .marking
+                .as_ref()
+                .map(|marking| marking.dead_stones.clone())
+                .unwrap_or_default(),
+            ..Overlay::default()
+        };
+        print!(
+            "{}",
+            session.renderer.render(&latest_boardstate.board, &overlay)
+        );
+
         println!("B: {}", latest_boardstate.black_prisoners);
         println!("W: {}", latest_boardstate.white_prisoners);
 
-        let command = inquire::Text::new("").prompt().unwrap();
+        println!(
+            "Move {} of {}",
+            gamestate.move_number(),
+            gamestate.total_moves()
+        );
+
+        let tree = gamestate.tree();
+        let node = tree.current_node();
+
+        if let Some(parent) = node.parent() {
+            let siblings = tree.node(parent).unwrap().children();
+
+            if siblings.len() > 1 {
+                let variation = siblings.iter().position(|&s| s == tree.current()).unwrap();
+                println!("Variation {} of {}", variation + 1, siblings.len());
+            }
+        }
+
+        if node.children().len() > 1 {
+            println!(
+                "{} variations follow, list them with variations",
+                node.children().len()
+            );
+        }
+
+        if !node.comment.is_empty() {
+            println!("{}", node.comment);
+        }
+
+        match gamestate.status {
+            GameStatus::Playing => {
+                if session.edit_mode {
+                    println!("Editing, {} to move", gamestate.to_move.name());
+                } else {
+                    println!("{} to move", gamestate.to_move.name());
+                }
+            }
+            GameStatus::Passed => {
+                println!("Game over, both players passed");
 
-        let res = parse_command(command, &mut gamestate);
+                if let Some(marking) = &session.marking {
+                    println!("Mark dead stones with mark {{position}}, then both players accept with accept b and accept w");
+                    print_score(&marking.score(latest_boardstate, &gamestate.ruleset));
+
+                    for player in marking.accepted.iter() {
+                        println!("{} accepted", player.name());
+                    }
+                }
+            }
+            GameStatus::Resigned(player) => {
+                println!(
+                    "Game over, {} wins by resignation",
+                    player.opposite().name()
+                );
+            }
+            GameStatus::Scored(report) => {
+                println!("Game over, {}", report);
+            }
+        }
+
+        let command = match inquire::Text::new("").prompt() {
+            Ok(command) => command,
+            Err(
+                inquire::InquireError::OperationCanceled
+                | inquire::InquireError::OperationInterrupted,
+            ) => std::process::exit(0),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let res = parse_command(command, &mut session);
         if let Err(e) = res {
             println!("{}", e);
         }
+
+        update_marking(&mut session);
     }
 }
 
-/// Parses a command and executes it;
-///
-/// Commands: "exit", "w/white|b/black;x;y", "u/undo"
-fn parse_command(input: String, gamestate: &mut GameState) -> Result<(), errors::GoError> {
-    let lower = input.to_lowercase();
+/// Starts marking dead stones after both players passed, and stops once the game goes on or is scored
+fn update_marking(session: &mut Session) {
+    match session.gamestate.status {
+        GameStatus::Passed => {
+            if session.marking.is_none() {
+                session.marking = Some(DeadStoneMarking::new());
+            }
+        }
+        _ => session.marking = None,
+    }
+}
 
-    if lower == "end" {
-        std::process::exit(1);
+const START_VARIATION: &str = "Start a variation";
+const OVERWRITE_LINE: &str = "Overwrite the rest of the line";
+
+/// Everything the client keeps track of between commands
+struct Session {
+    gamestate: GameState,
+    /// In edit mode, stones are placed freely to set up a position
+    edit_mode: bool,
+    /// Dead stones marked after both players passed
+    marking: Option<DeadStoneMarking>,
+    /// A bot or GTP engine we play against
+    opponent: Option<Box<dyn Player>>,
+    /// The colour the opponent plays
+    opponent_color: IntersectionState,
+    /// Draws the board
+    renderer: Box<dyn Renderer>,
+}
+
+impl Session {
+    /// Returns the name of the opponent, or an empty string without one
+    fn opponent_name(&self) -> String {
+        self.opponent
+            .as_ref()
+            .map(|opponent| opponent.name())
+            .unwrap_or_default()
     }
+}
 
-    if lower == "exit" || lower == "quit" {
-        std::process::exit(0);
+/// Parses a board size like "19", or "13x9" for a board 13 wide and 9 high
+fn parse_board_size(size: &str) -> Option<(usize, usize)> {
+    match size.split_once(['x', 'X']) {
+        Some((width, height)) => Some((width.parse().ok()?, height.parse().ok()?)),
+        None => {
+            let size = size.parse().ok()?;
+            Some((size, size))
+        }
     }
+}
 
-    // in;5;5
-    // info;5;5
-    // Prints stone liberties
-    if lower.contains("in") || lower.contains("info") {
-        let params = lower
-            .replace(" ", "")
-            .split(";")
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>();
+/// Parses the colour given to a command line flag, exiting if it isn't one
+fn parse_color_argument(flag: &str, color: Option<String>) -> IntersectionState {
+    match color.as_deref() {
+        Some("w" | "white") => IntersectionState::White,
+        Some("b" | "black") => IntersectionState::Black,
+        _ => {
+            println!("{} needs to be b/black or w/white", flag);
+            std::process::exit(1);
+        }
+    }
+}
 
-        let x_as_ascii = params
-            .get(1)
-            .unwrap()
-            .to_uppercase()
-            .chars()
-            .nth(0)
-            .unwrap();
+/// Lets the opponent play the given colour, putting its name into the game info
+fn set_opponent(session: &mut Session, opponent: Box<dyn Player>, color: IntersectionState) {
+    match color {
+        IntersectionState::White => session.gamestate.info.white_player = opponent.name(),
+        _ => session.gamestate.info.black_player = opponent.name(),
+    }
 
-        let mut asci_chars = ASCII.chars();
+    session.opponent = Some(opponent);
+    session.opponent_color = color;
+}
 
-        let mut x_res: Option<usize> = None;
+/// Lets the opponent move if it's its turn, in a position nobody has played on from yet
+fn play_opponent_move(session: &mut Session) -> Result<(), errors::GoError> {
+    let Some(opponent) = session.opponent.as_mut() else {
+        return Ok(());
+    };
+    let gamestate = &session.gamestate;
 
-        for i in 0..ASCII.len() {
-            if asci_chars.next().unwrap() == x_as_ascii {
-                x_res = Some(i);
-                break;
-            }
-        }
+    // After an undo, the undone moves are still there, so we don't play over them
+    let fresh = gamestate.tree().current_node().children().is_empty();
 
-        let y_res = params.get(2).unwrap().parse::<usize>();
+    if gamestate.to_move != session.opponent_color
+        || gamestate.status.is_over()
+        || session.edit_mode
+        || !fresh
+    {
+        return Ok(());
+    }
 
-        if x_res.is_none() || y_res.is_err() {
-            return Err(errors::GoError::InvalidMove);
-        }
+    let command = opponent.genmove(gamestate)?;
+    session.gamestate = gamestate.apply_command(command)?;
+    Ok(())
+}
 
-        let x = x_res.unwrap();
-        let y = y_res.unwrap() - 1;
+/// Parses a command and executes it; help lists the commands
+fn parse_command(input: String, session: &mut Session) -> Result<(), errors::GoError> {
+    if input.trim().is_empty() {
+        return Ok(());
+    }
 
-        let latest_state = gamestate.history.get(gamestate.history.len() - 1).unwrap();
+    let board = &session.gamestate.current().board;
+    let command = Command::parse(&input, board.width(), board.height())?;
+    let gamestate = &mut session.gamestate;
 
-        let direct = latest_state.board.interesection_direct_liberties(x, y);
+    match command {
+        Command::Game(command) => return apply_game_command(command, session, ask_overwrite),
+        Command::Resign(player) => {
+            let player = player.unwrap_or(gamestate.to_move);
+            return apply_game_command(GameCommand::Resign(player), session, ask_overwrite);
+        }
+        Command::Next(variation) => return gamestate.to_child(variation),
+        Command::Back => return gamestate.to_parent(),
+        Command::Variations => {
+            let tree = gamestate.tree();
+            let height = tree.current_node().boardstate.board.height();
 
-        if let Err(e) = direct {
-            return Err(e);
+            for (variation, &child) in tree.current_node().children().iter().enumerate() {
+                if let Some(node) = tree.node(child) {
+                    let last_move = node.boardstate.last_move;
+                    println!("{}: {}", variation + 1, describe_move(last_move, height));
+                }
+            }
         }
+        Command::Variation(variation) => return gamestate.to_sibling(variation),
+        Command::Promote => gamestate.promote_variation(),
+        Command::Info(x, y) => {
+            let board = &gamestate.current().board;
+            let direct = board.interesection_direct_liberties(x, y)?;
+            let full = board.find_true_liberties(x, y)?;
 
-        let full = latest_state.board.find_true_liberties(x, y);
+            println!("Direct: {}, True: {}", direct.len(), full.len());
+        }
+        Command::Score(scoring_rule) => {
+            let mut ruleset = gamestate.ruleset;
 
-        if let Err(e) = full {
-            return Err(e);
+            if let Some(scoring_rule) = scoring_rule {
+                ruleset.scoring_rule = scoring_rule;
+            }
+
+            print_score(&scoring::score(
+                gamestate.current(),
+                &HashSet::new(),
+                &ruleset,
+            ));
+        }
+        Command::Mark(x, y) => {
+            let marking = session.marking.as_mut().ok_or(errors::GoError::NotOver)?;
+            marking.toggle_group(&gamestate.current().board, x, y)?;
         }
+        Command::Accept(player) => {
+            let marking = session.marking.as_mut().ok_or(errors::GoError::NotOver)?;
+            marking.accept(player);
 
-        println!(
-            "Direct: {}, True: {}",
-            direct.unwrap().len(),
-            full.unwrap().len()
-        );
-        return Ok(());
+            if marking.is_agreed() {
+                let report = marking.score(gamestate.current(), &gamestate.ruleset);
+                gamestate.status = GameStatus::Scored(report);
+            }
+        }
+        Command::Rules(ruleset) => {
+            gamestate.ruleset = ruleset;
+            println!("Rules: {:?}", gamestate.ruleset);
+        }
+        Command::Ko(ko_rule) => {
+            gamestate.ruleset.ko_rule = ko_rule;
+            println!("Ko rule: {:?}", gamestate.ruleset.ko_rule);
+        }
+        Command::Komi(komi) => gamestate.ruleset.komi = komi,
+        Command::Edit => session.edit_mode = !session.edit_mode,
+        Command::Turn(player) => gamestate.to_move = player,
+        Command::Comment(comment) => gamestate.set_comment(comment),
+        Command::Name(player, name) => match player {
+            IntersectionState::White => gamestate.info.white_player = name,
+            _ => gamestate.info.black_player = name,
+        },
+        Command::Save(path) => {
+            std::fs::write(&path, sgf::to_sgf(gamestate)?).map_err(|e| {
+                errors::GoError::FileError {
+                    path: path.clone(),
+                    message: e.to_string(),
+                }
+            })?;
+            println!("Saved to {}", path);
+        }
+        Command::Load(path) => return load_game(&path, session),
+        Command::Help(Some(help)) => println!("{}", help),
+        Command::Help(None) => {
+            for help in COMMANDS {
+                println!("{}", help);
+            }
+            println!("Positions are written like D4, with columns skipping I and rows counted from the bottom");
+        }
+        Command::Exit => std::process::exit(0),
     }
 
-    // Commands that mutate the gamestate, as they have their own parsing
-    if let Some(command) = GameCommand::try_from_string(input) {
-        let gamestate_result = gamestate.apply_command(command);
+    Ok(())
+}
+
+/// Asks whether a new move should overwrite the rest of the line, instead of starting a variation
+fn ask_overwrite() -> bool {
+    let choice = inquire::Select::new(
+        "The line already continues from here",
+        vec![START_VARIATION, OVERWRITE_LINE],
+    )
+    .prompt();
+
+    matches!(choice, Ok(OVERWRITE_LINE))
+}
+
+/// Applies a command which changes the game;
+///
+/// When a new move is played where the line already continues, overwrite decides whether it replaces the rest of the line.
+fn apply_game_command(
+    mut command: GameCommand,
+    session: &mut Session,
+    overwrite: impl FnOnce() -> bool,
+) -> Result<(), errors::GoError> {
+    let gamestate = &mut session.gamestate;
 
-        if gamestate_result.is_none() {
-            return Err(errors::GoError::InvalidMove);
+    if session.edit_mode {
+        if let GameCommand::Set(x, y, state) = command {
+            command = GameCommand::Setup(x, y, state);
         }
+    }
+
+    let mut new_gamestate = gamestate.apply_command(command)?;
+
+    // A new move where the line already went on, so decide what to do with the rest of it
+    let continued = !gamestate.tree().current_node().children().is_empty();
+
+    if continued && new_gamestate.tree().len() > gamestate.tree().len() && overwrite() {
+        new_gamestate.overwrite_line();
+    }
 
-        let new_gamestate = gamestate_result.unwrap();
-        let _ = std::mem::replace(gamestate, new_gamestate);
+    // Against an opponent, undo back to our own turn
+    if command == GameCommand::Undo
+        && session.opponent.is_some()
+        && new_gamestate.to_move == session.opponent_color
+    {
+        if let Ok(undone) = new_gamestate.apply_command(GameCommand::Undo) {
+            new_gamestate = undone;
+        }
     }
 
+    let _ = std::mem::replace(gamestate, new_gamestate);
     Ok(())
 }
+
+/// Loads an SGF file, starting from the position before the first move
+fn load_game(path: &str, session: &mut Session) -> Result<(), errors::GoError> {
+    let text = std::fs::read_to_string(path).map_err(|e| errors::GoError::FileError {
+        path: path.to_string(),
+        message: e.to_string(),
+    })?;
+
+    // The start is after the setup stones, so problems show their position
+    session.gamestate = sgf::from_sgf(&text)?.apply_command(GameCommand::First)?;
+    session.edit_mode = false;
+    Ok(())
+}
+
+/// Describes a move in the format of the board labels, like "Black D4"
+fn describe_move(last_move: Option<GameCommand>, height: usize) -> String {
+    match last_move {
+        Some(GameCommand::Set(x, y, state)) => {
+            format!(
+                "{} {}",
+                state.name(),
+                coordinates::position_name(x, y, height)
+            )
+        }
+        Some(GameCommand::Setup(..)) => String::from("Setup"),
+        Some(GameCommand::Pass) => String::from("Pass"),
+        Some(GameCommand::Resign(state)) => format!("{} resigns", state.name()),
+        Some(GameCommand::Resume) => String::from("Resume"),
+        _ => String::new(),
+    }
+}
+
+/// Prints a score breakdown for both players
+fn print_score(report: &ScoreReport) {
+    match report.method {
+        ScoringRule::Area => {
+            println!(
+                "B: {} stones + {} territory = {}",
+                report.black.stones,
+                report.black.territory,
+                report.black.total()
+            );
+            println!(
+                "W: {} stones + {} territory + {} komi = {}",
+                report.white.stones,
+                report.white.territory,
+                report.white.komi,
+                report.white.total()
+            );
+        }
+        ScoringRule::Territory => {
+            println!(
+                "B: {} territory + {} prisoners + {} dead = {}",
+                report.black.territory,
+                report.black.prisoners,
+                report.black.dead_stones,
+                report.black.total()
+            );
+            println!(
+                "W: {} territory + {} prisoners + {} dead + {} komi = {}",
+                report.white.territory,
+                report.white.prisoners,
+                report.white.dead_stones,
+                report.white.komi,
+                report.white.total()
+            );
+        }
+    }
+    println!("{}", report);
+}
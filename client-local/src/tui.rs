@@ -0,0 +1,462 @@
+use crate::{apply_game_command, play_opponent_move, update_marking, Session};
+
+use crossterm::{
+    cursor,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
+    queue,
+    style::{Attribute, SetAttribute},
+    terminal::{self, ClearType},
+};
+use gorst_common::{
+    board::IntersectionState,
+    coordinates,
+    errors::GoError,
+    gamecommand::GameCommand,
+    gamestate::GameStatus,
+    marking::DeadStoneMarking,
+    rendering::{self, Overlay},
+    scoring,
+};
+
+use std::collections::HashSet;
+use std::io::Write;
+
+/// The hotkeys, as the bottom line of the screen lists them
+const HOTKEYS: &str =
+    "arrows/hjkl move  enter/space play  u undo  r redo  p pass  s score  m mark  a accept  q quit";
+
+/// Switches the terminal to raw input with mouse events on an alternate screen, and back when dropped
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> std::io::Result<RawTerminal> {
+        terminal::enable_raw_mode()?;
+
+        let mut stdout = std::io::stdout();
+        queue!(
+            stdout,
+            terminal::EnterAlternateScreen,
+            EnableMouseCapture,
+            cursor::Hide
+        )?;
+        stdout.flush()?;
+
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        // Nothing left to report errors to while restoring the terminal
+        let mut stdout = std::io::stdout();
+        let _ = queue!(
+            stdout,
+            cursor::Show,
+            DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What the full-screen interface keeps track of besides the session
+struct Screen {
+    /// The intersection moves are played at
+    cursor: (usize, usize),
+    /// The intersection under the mouse, where terminals report mouse motion
+    hover: Option<(usize, usize)>,
+    /// Shown in the status bar, like the last error or the score
+    message: String,
+}
+
+/// Returns the player who accepts the marking next, when both share the keyboard
+fn next_to_accept(marking: &DeadStoneMarking) -> Option<IntersectionState> {
+    [IntersectionState::Black, IntersectionState::White]
+        .into_iter()
+        .find(|player| !marking.accepted.contains(player))
+}
+
+/// Plays in a full-screen interface until the player quits
+pub fn run(session: &mut Session) -> std::io::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+
+    let board = &session.gamestate.current().board;
+    let mut screen = Screen {
+        cursor: (board.width() / 2, board.height() / 2),
+        hover: None,
+        message: String::new(),
+    };
+
+    loop {
+        if let Err(e) = play_opponent_move(session) {
+            screen.message = format!("{}; stopped playing against {}", e, session.opponent_name());
+            session.opponent = None;
+        }
+
+        update_marking(session);
+        screen.draw(session)?;
+
+        let running = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => screen.handle_key(key, session),
+            Event::Mouse(mouse) => {
+                screen.handle_mouse(mouse, session);
+                true
+            }
+            _ => true,
+        };
+
+        if !running {
+            return Ok(());
+        }
+    }
+}
+
+impl Screen {
+    /// Handles a key press, returning false to quit
+    fn handle_key(&mut self, key: KeyEvent, session: &mut Session) -> bool {
+        let board = &session.gamestate.current().board;
+        let (x, y) = self.cursor;
+
+        let result = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.cursor.0 = x.saturating_sub(1);
+                return true;
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.cursor.0 = (x + 1).min(board.width().saturating_sub(1));
+                return true;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.cursor.1 = y.saturating_sub(1);
+                return true;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor.1 = (y + 1).min(board.height().saturating_sub(1));
+                return true;
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let to_move = session.gamestate.to_move;
+                // New moves in a line which already continues start a variation
+                apply_game_command(GameCommand::Set(x, y, to_move), session, || false)
+            }
+            KeyCode::Char('u') => apply_game_command(GameCommand::Undo, session, || false),
+            KeyCode::Char('r') => apply_game_command(GameCommand::Redo, session, || false),
+            KeyCode::Char('p') => apply_game_command(GameCommand::Pass, session, || false),
+            KeyCode::Char('s') => {
+                let gamestate = &session.gamestate;
+                let report = match &session.marking {
+                    Some(marking) => marking.score(gamestate.current(), &gamestate.ruleset),
+                    None => {
+                        scoring::score(gamestate.current(), &HashSet::new(), &gamestate.ruleset)
+                    }
+                };
+
+                self.message = report.to_string();
+                return true;
+            }
+            KeyCode::Char('m') => match session.marking.as_mut() {
+                Some(marking) => marking.toggle_group(board, x, y),
+                None => Err(GoError::NotOver),
+            },
+            KeyCode::Char('a') => self.accept(session),
+            _ => return true,
+        };
+
+        self.message = match result {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        };
+        true
+    }
+
+    /// Plays where the board is clicked, and follows the mouse to preview the move
+    fn handle_mouse(&mut self, mouse: MouseEvent, session: &mut Session) {
+        let board = &session.gamestate.current().board;
+        let intersection =
+            rendering::intersection_at(board, mouse.column as usize, mouse.row as usize);
+
+        match mouse.kind {
+            MouseEventKind::Moved | MouseEventKind::Drag(_) => self.hover = intersection,
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some((x, y)) = intersection else {
+                    return;
+                };
+                self.cursor = (x, y);
+
+                let to_move = session.gamestate.to_move;
+                let result = apply_game_command(GameCommand::Set(x, y, to_move), session, || false);
+
+                self.message = match result {
+                    Ok(()) => String::new(),
+                    Err(e) => e.to_string(),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Accepts the dead stone marking for the next player, as both share the keyboard and accept in turn;
+    /// Bots and engines don't mark stones themselves, so they accept whatever the player marked.
+    fn accept(&mut self, session: &mut Session) -> Result<(), GoError> {
+        let gamestate = &mut session.gamestate;
+        let marking = session.marking.as_mut().ok_or(GoError::NotOver)?;
+
+        if session.opponent.is_some() {
+            marking.accept(session.opponent_color.opposite());
+            marking.accept(session.opponent_color);
+        } else if let Some(player) = next_to_accept(marking) {
+            marking.accept(player);
+        }
+
+        if marking.is_agreed() {
+            let report = marking.score(gamestate.current(), &gamestate.ruleset);
+            gamestate.status = GameStatus::Scored(report);
+        }
+
+        Ok(())
+    }
+
+    /// Describes the state of the game for the status bar
+    fn status(&self, session: &Session) -> String {
+        let gamestate = &session.gamestate;
+
+        match gamestate.status {
+            GameStatus::Playing if session.edit_mode => {
+                format!("Editing, {} to move", gamestate.to_move.name())
+            }
+            GameStatus::Playing => format!("{} to move", gamestate.to_move.name()),
+            GameStatus::Passed => match session.marking.as_ref().and_then(next_to_accept) {
+                Some(player) if session.opponent.is_none() => format!(
+                    "Both passed, mark dead stones with m, then {} accepts with a",
+                    player.name()
+                ),
+                _ => String::from("Both passed, mark dead stones with m, then accept with a"),
+            },
+            GameStatus::Resigned(player) => {
+                format!("{} wins by resignation", player.opposite().name())
+            }
+            GameStatus::Scored(report) => report.to_string(),
+        }
+    }
+
+    /// Draws the board with the cursor, followed by the status bar and the hotkeys
+    fn draw(&self, session: &Session) -> std::io::Result<()> {
+        let gamestate = &session.gamestate;
+        let boardstate = gamestate.current();
+        let board = &boardstate.board;
+
+        let (x, y) = self.cursor;
+
+        let dead_stones = session
+            .marking
+            .as_ref()
+            .map(|marking| marking.dead_stones.clone())
+            .unwrap_or_default();
+        // Preview the move under the mouse, if one could be played there
+        let ghost = self.hover.filter(|&(x, y)| {
+            gamestate.status == GameStatus::Playing
+                && board.get_intersection(x, y) == Some(IntersectionState::Empty)
+        });
+
+        let overlay = Overlay {
+            marked: dead_stones,
+            cursor: Some(self.cursor),
+            ghost: ghost.map(|(x, y)| (x, y, gamestate.to_move)),
+        };
+        let drawn = session.renderer.render(board, &overlay);
+
+        // The drawing ends with a line that only resets the colours, which separates the status bar
+        let mut lines: Vec<String> = drawn.lines().map(String::from).collect();
+        lines.push(format!(
+            "B: {}  W: {}  Move {} of {}  {}  {}",
+            boardstate.black_prisoners,
+            boardstate.white_prisoners,
+            gamestate.move_number(),
+            gamestate.total_moves(),
+            coordinates::position_name(x, y, board.height()),
+            self.status(session)
+        ));
+        lines.push(self.message.clone());
+        lines.push(HOTKEYS.to_string());
+
+        let mut stdout = std::io::stdout();
+        queue!(stdout, cursor::MoveTo(0, 0))?;
+
+        let status_bar = lines.len() - 3;
+        for (index, line) in lines.iter().enumerate() {
+            if index == status_bar {
+                queue!(stdout, SetAttribute(Attribute::Reverse))?;
+                write!(stdout, "{}", line)?;
+                queue!(stdout, SetAttribute(Attribute::Reset))?;
+            } else {
+                write!(stdout, "{}", line)?;
+            }
+
+            queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+            write!(stdout, "\r\n")?;
+        }
+
+        queue!(stdout, terminal::Clear(ClearType::FromCursorDown))?;
+        stdout.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Screen;
+    use crate::{update_marking, Session};
+
+    use crossterm::event::{
+        KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+    use gorst_common::{
+        board::{Board, IntersectionState},
+        gamestate::{GameState, GameStatus},
+        player::RandomPlayer,
+        rendering::AnsiRenderer,
+    };
+
+    fn new_session(size: usize) -> Session {
+        Session {
+            gamestate: GameState::with_board(Board::new(size)),
+            edit_mode: false,
+            marking: None,
+            opponent: None,
+            opponent_color: IntersectionState::White,
+            renderer: Box::new(AnsiRenderer),
+        }
+    }
+
+    fn new_screen(cursor: (usize, usize)) -> Screen {
+        Screen {
+            cursor,
+            hover: None,
+            message: String::new(),
+        }
+    }
+
+    /// Presses the given keys one after another, returning whether the last one kept the screen running
+    fn press(screen: &mut Screen, session: &mut Session, keys: &[KeyCode]) -> bool {
+        keys.iter().all(|&code| {
+            let running = screen.handle_key(KeyEvent::new(code, KeyModifiers::NONE), session);
+            update_marking(session);
+            running
+        })
+    }
+
+    #[test]
+    pub fn cursor_stays_on_the_board() {
+        let mut session = new_session(3);
+        let mut screen = new_screen((1, 1));
+
+        press(&mut screen, &mut session, &[KeyCode::Right; 4]);
+        assert_eq!(screen.cursor, (2, 1));
+        press(&mut screen, &mut session, &[KeyCode::Char('k'); 4]);
+        assert_eq!(screen.cursor, (2, 0));
+        press(&mut screen, &mut session, &[KeyCode::Char('h'); 4]);
+        assert_eq!(screen.cursor, (0, 0));
+        press(&mut screen, &mut session, &[KeyCode::Down; 4]);
+        assert_eq!(screen.cursor, (0, 2));
+
+        // A board without intersections keeps the cursor where it is instead of panicking
+        let mut session = new_session(0);
+        let mut screen = new_screen((0, 0));
+        press(&mut screen, &mut session, &[KeyCode::Right, KeyCode::Down]);
+        assert_eq!(screen.cursor, (0, 0));
+    }
+
+    #[test]
+    pub fn play_undo_and_pass() {
+        let mut session = new_session(5);
+        let mut screen = new_screen((2, 2));
+
+        assert!(press(&mut screen, &mut session, &[KeyCode::Enter]));
+        let board = &session.gamestate.current().board;
+        assert_eq!(board.get_intersection(2, 2), Some(IntersectionState::Black));
+        assert_eq!(session.gamestate.to_move, IntersectionState::White);
+
+        // Playing on a stone shows the error instead
+        press(&mut screen, &mut session, &[KeyCode::Char(' ')]);
+        assert_eq!(screen.message, "Intersection C3 is already occupied");
+
+        press(&mut screen, &mut session, &[KeyCode::Char('u')]);
+        let board = &session.gamestate.current().board;
+        assert_eq!(board.get_intersection(2, 2), Some(IntersectionState::Empty));
+        assert_eq!(screen.message, "");
+
+        press(&mut screen, &mut session, &[KeyCode::Char('r')]);
+        press(&mut screen, &mut session, &[KeyCode::Char('p')]);
+        assert_eq!(session.gamestate.to_move, IntersectionState::Black);
+        assert_eq!(session.gamestate.move_number(), 2);
+
+        assert!(!press(&mut screen, &mut session, &[KeyCode::Char('q')]));
+    }
+
+    #[test]
+    pub fn accept_against_an_opponent() {
+        let mut session = new_session(5);
+        session.opponent = Some(Box::new(RandomPlayer::with_seed(1)));
+        let mut screen = new_screen((2, 2));
+
+        press(&mut screen, &mut session, &[KeyCode::Char('p'); 2]);
+        assert_eq!(session.gamestate.status, GameStatus::Passed);
+
+        press(&mut screen, &mut session, &[KeyCode::Char('a')]);
+        assert!(matches!(session.gamestate.status, GameStatus::Scored(_)));
+    }
+
+    #[test]
+    pub fn both_players_accept() {
+        let mut session = new_session(5);
+        let mut screen = new_screen((2, 2));
+
+        press(&mut screen, &mut session, &[KeyCode::Char('p'); 2]);
+        assert!(screen.status(&session).contains("Black accepts"));
+
+        // A single press only accepts for black
+        press(&mut screen, &mut session, &[KeyCode::Char('a')]);
+        assert_eq!(session.gamestate.status, GameStatus::Passed);
+        assert!(screen.status(&session).contains("White accepts"));
+
+        press(&mut screen, &mut session, &[KeyCode::Char('a')]);
+        assert!(matches!(session.gamestate.status, GameStatus::Scored(_)));
+    }
+
+    #[test]
+    pub fn click_and_hover() {
+        let mut session = new_session(5);
+        let mut screen = new_screen((0, 0));
+
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let click = |column, row| mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+
+        // Hovering follows the mouse, and leaves the board with it
+        screen.handle_mouse(mouse(MouseEventKind::Moved, 5, 3), &mut session);
+        assert_eq!(screen.hover, Some((2, 2)));
+        screen.handle_mouse(mouse(MouseEventKind::Moved, 4, 3), &mut session);
+        assert_eq!(screen.hover, None);
+
+        // Clicking the labels or the lines between intersections does nothing
+        for (column, row) in [(5, 0), (4, 3), (0, 3), (11, 3), (5, 6), (500, 500)] {
+            screen.handle_mouse(click(column, row), &mut session);
+        }
+        assert_eq!(session.gamestate.move_number(), 0);
+        assert_eq!(screen.cursor, (0, 0));
+
+        screen.handle_mouse(click(5, 3), &mut session);
+        let board = &session.gamestate.current().board;
+        assert_eq!(board.get_intersection(2, 2), Some(IntersectionState::Black));
+        assert_eq!(screen.cursor, (2, 2));
+    }
+}
@@ -1,9 +1,10 @@
 use crate::errors;
 use crate::rendering;
+use crate::zobrist;
 use std::collections::HashSet;
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, PartialOrd, Ord)]
 /// Describes possible state of an intersection;
 /// It can be empty, filled with a black stone or filled with a white stone.
 pub enum IntersectionState {
@@ -13,11 +14,32 @@ pub enum IntersectionState {
     White,
 }
 
+impl IntersectionState {
+    /// Returns the state of the opposing player;
+    /// Empty stays empty.
+    pub fn opposite(&self) -> IntersectionState {
+        match self {
+            Self::Empty => Self::Empty,
+            Self::Black => Self::White,
+            Self::White => Self::Black,
+        }
+    }
+
+    /// Returns the name of the state, as displayed to players
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Black => "Black",
+            Self::White => "White",
+        }
+    }
+}
+
 impl fmt::Display for IntersectionState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Empty => {
-                write!(f, "{}", " ")
+                write!(f, " ")
             }
             Self::Black => {
                 write!(f, "{}", rendering::BLACK_STONE)
@@ -29,53 +51,326 @@ impl fmt::Display for IntersectionState {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+/// Marks an intersection which isn't part of any chain
+const NO_CHAIN: usize = usize::MAX;
+
+#[derive(Clone)]
 /// Describes a go board
+///
+/// Stones which are connected form chains, which are kept up to date as stones are placed and removed,
+/// so finding a group or checking whether it has liberties doesn't need to search the board.
+/// Chains refer to stones by their index in the flat array, so boards of any size work.
 pub struct Board {
-    /// Data, stored in y, x format;
-    /// A vector of rows;
-    data: Vec<Vec<IntersectionState>>,
+    width: usize,
+    height: usize,
+    /// The intersections in one flat array, row by row;
+    /// (x, y) is at y * width + x.
+    cells: Vec<IntersectionState>,
+    /// The stone representing the chain every stone belongs to, or NO_CHAIN for empty intersections;
+    /// When two chains join, the smaller one is relabelled, so this always points at the representative.
+    chains: Vec<usize>,
+    /// Links the stones of every chain into a circle, so a chain can be walked without searching
+    next: Vec<usize>,
+    /// The number of stones in a chain, stored at its representative
+    stones: Vec<usize>,
+    /// The pseudo-liberties of a chain, stored at its representative;
+    /// An empty intersection counts once for every stone of the chain next to it,
+    /// so this is zero exactly when the chain has no liberties.
+    pseudo_liberties: Vec<usize>,
+    /// The Zobrist hash of the stones on the board, updated with every stone placed or removed
+    hash: u64,
 }
 
 impl Board {
-    /// Returns the size of the board
+    /// Returns the size of a square board;
+    /// For rectangular boards this is the longer side, so use width and height instead.
     pub fn size(&self) -> usize {
-        self.data.len()
+        self.width.max(self.height)
+    }
+
+    /// Returns the number of intersections in a row, the range of x
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows, the range of y
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether the board is as wide as it is high
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
     }
 
-    /// Clones and returns inner data.
+    /// Returns the Zobrist hash of the stones on the board.
+    ///
+    /// Boards with the same stones have the same hash, and different ones almost certainly don't.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the intersections as a vector of rows, in y, x format
     pub fn get_data(&self) -> Vec<Vec<IntersectionState>> {
-        return self.data.clone();
+        (0..self.height)
+            .map(|y| self.cells[y * self.width..(y + 1) * self.width].to_vec())
+            .collect()
     }
 
-    /// Overrides the board to the given data
+    /// Overrides the board to the given data, a vector of rows;
+    /// The board is as wide as the longest row.
     pub fn set_data(&mut self, data: Vec<Vec<IntersectionState>>) {
-        self.data = data;
+        let width = data.iter().map(Vec::len).max().unwrap_or_default();
+        *self = Board::new_rect(width, data.len());
+
+        for (y, row) in data.iter().enumerate() {
+            for (x, state) in row.iter().enumerate() {
+                if *state != IntersectionState::Empty {
+                    self.place_stone(self.index(x, y), *state);
+                }
+            }
+        }
     }
 
     /// Creates a new board, size x size big
     pub fn new(size: usize) -> Board {
-        let mut board = Board { data: Vec::new() };
+        Self::new_rect(size, size)
+    }
+
+    /// Creates a new rectangular board, width x height big
+    pub fn new_rect(width: usize, height: usize) -> Board {
+        let points = width * height;
+
+        Board {
+            width,
+            height,
+            cells: vec![IntersectionState::Empty; points],
+            chains: vec![NO_CHAIN; points],
+            next: vec![NO_CHAIN; points],
+            stones: vec![0; points],
+            pseudo_liberties: vec![0; points],
+            hash: 0,
+        }
+    }
+
+    /// Returns the index of a position in the flat array
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    /// Returns the position of an index in the flat array
+    fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    /// Returns the indices above, to the left, to the right and below an index, where they are on the board
+    fn neighbour_indices(&self, index: usize) -> impl Iterator<Item = usize> {
+        let (x, y) = self.position(index);
+        let (width, height) = (self.width, self.height);
+
+        [
+            (y > 0).then(|| index - width),
+            (x > 0).then(|| index - 1),
+            (x + 1 < width).then(|| index + 1),
+            (y + 1 < height).then(|| index + width),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Returns the indices of the stones in the chain with the given representative
+    fn chain_stones(&self, chain: usize) -> Vec<usize> {
+        let mut stones = Vec::with_capacity(self.stones[chain]);
+        let mut stone = chain;
+
+        loop {
+            stones.push(stone);
+            stone = self.next[stone];
+
+            if stone == chain {
+                break;
+            }
+        }
+
+        stones
+    }
+
+    /// Puts a stone on an empty index, joining it with the chains next to it
+    fn place_stone(&mut self, index: usize, state: IntersectionState) {
+        self.cells[index] = state;
+        self.hash ^= zobrist::stone_key(index, state);
+        self.chains[index] = index;
+        self.next[index] = index;
+        self.stones[index] = 1;
+        self.pseudo_liberties[index] = 0;
+
+        for neighbour in self.neighbour_indices(index) {
+            match self.cells[neighbour] {
+                IntersectionState::Empty => self.pseudo_liberties[index] += 1,
+                _ => self.pseudo_liberties[self.chains[neighbour]] -= 1,
+            }
+        }
+
+        for neighbour in self.neighbour_indices(index) {
+            if self.cells[neighbour] == state {
+                self.join_chains(self.chains[index], self.chains[neighbour]);
+            }
+        }
+    }
+
+    /// Joins two chains, relabelling the smaller one
+    fn join_chains(&mut self, first: usize, second: usize) {
+        if first == second {
+            return;
+        }
+
+        let (large, small) = if self.stones[first] >= self.stones[second] {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        for stone in self.chain_stones(small) {
+            self.chains[stone] = large;
+        }
+
+        // Splicing two circular lists together is a swap
+        self.next.swap(large, small);
+
+        self.stones[large] += self.stones[small];
+        self.pseudo_liberties[large] += self.pseudo_liberties[small];
+    }
+
+    /// Removes every stone of a chain, returning how many there were
+    fn remove_chain(&mut self, chain: usize) -> usize {
+        let stones = self.chain_stones(chain);
 
-        for _y in 0..size {
-            let mut row = Vec::new();
-            for _x in 0..size {
-                row.push(IntersectionState::Empty);
+        for &stone in stones.iter() {
+            self.hash ^= zobrist::stone_key(stone, self.cells[stone]);
+            self.cells[stone] = IntersectionState::Empty;
+            self.chains[stone] = NO_CHAIN;
+        }
+
+        for &stone in stones.iter() {
+            for neighbour in self.neighbour_indices(stone) {
+                if self.cells[neighbour] != IntersectionState::Empty {
+                    self.pseudo_liberties[self.chains[neighbour]] += 1;
+                }
             }
-            board.data.push(row);
         }
-        return board;
+
+        stones.len()
+    }
+
+    /// Removes a single stone, which may split its chain into several
+    fn remove_stone(&mut self, index: usize) {
+        let state = self.cells[index];
+        let stones = self.chain_stones(self.chains[index]);
+
+        for &stone in stones.iter() {
+            self.chains[stone] = NO_CHAIN;
+        }
+        self.cells[index] = IntersectionState::Empty;
+        self.hash ^= zobrist::stone_key(index, state);
+
+        for &stone in stones.iter() {
+            if stone != index && self.chains[stone] == NO_CHAIN {
+                self.rebuild_chain(stone);
+            }
+        }
+
+        for neighbour in self.neighbour_indices(index) {
+            let neighbour_state = self.cells[neighbour];
+
+            if neighbour_state != IntersectionState::Empty && neighbour_state != state {
+                self.pseudo_liberties[self.chains[neighbour]] += 1;
+            }
+        }
+    }
+
+    /// Finds the chain of a stone which isn't part of any chain yet, and labels it
+    fn rebuild_chain(&mut self, start: usize) {
+        let state = self.cells[start];
+        let mut stones = vec![start];
+        let mut to_visit = vec![start];
+        self.chains[start] = start;
+
+        let mut pseudo_liberties = 0;
+
+        while let Some(stone) = to_visit.pop() {
+            for neighbour in self.neighbour_indices(stone) {
+                if self.cells[neighbour] == IntersectionState::Empty {
+                    pseudo_liberties += 1;
+                } else if self.cells[neighbour] == state && self.chains[neighbour] == NO_CHAIN {
+                    self.chains[neighbour] = start;
+                    stones.push(neighbour);
+                    to_visit.push(neighbour);
+                }
+            }
+        }
+
+        for (i, &stone) in stones.iter().enumerate() {
+            self.next[stone] = stones[(i + 1) % stones.len()];
+        }
+
+        self.stones[start] = stones.len();
+        self.pseudo_liberties[start] = pseudo_liberties;
+    }
+
+    /// Counts the liberties of a chain, but only up to two, which is all that legality checks need
+    fn liberties_up_to_two(&self, chain: usize) -> usize {
+        // Pseudo-liberties only overcount, so few of them are exact
+        if self.pseudo_liberties[chain] <= 1 {
+            return self.pseudo_liberties[chain];
+        }
+
+        let mut found = None;
+        let mut stone = chain;
+
+        loop {
+            for neighbour in self.neighbour_indices(stone) {
+                if self.cells[neighbour] != IntersectionState::Empty {
+                    continue;
+                }
+
+                match found {
+                    None => found = Some(neighbour),
+                    Some(liberty) if liberty != neighbour => return 2,
+                    _ => {}
+                }
+            }
+
+            stone = self.next[stone];
+
+            if stone == chain {
+                break;
+            }
+        }
+
+        usize::from(found.is_some())
+    }
+
+    /// Returns an error if a position isn't on the board
+    fn check_position(&self, x: usize, y: usize) -> Result<(), errors::GoError> {
+        if x >= self.width || y >= self.height {
+            return Err(errors::GoError::InvalidPosition {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+
+        Ok(())
     }
 
     /// Returns the state of the intersection at x, y
     pub fn get_intersection(&self, x: usize, y: usize) -> Option<IntersectionState> {
-        if x >= self.size() || y >= self.size() {
+        if x >= self.width || y >= self.height {
             return None;
         }
 
-        let state = self.data.get(y).unwrap().get(x).unwrap().clone();
-
-        return Some(state);
+        Some(self.cells[self.index(x, y)])
     }
 
     /// Sets a state of an intersection, without performing any checks.
@@ -86,19 +381,49 @@ impl Board {
         y: usize,
         state: IntersectionState,
     ) -> Result<(), errors::GoError> {
-        if x >= self.size() || y >= self.size() {
-            return Err(errors::GoError::InvalidPosition {
-                x,
-                y,
-                size: self.size(),
-            });
+        self.check_position(x, y)?;
+
+        let index = self.index(x, y);
+
+        if self.cells[index] == state {
+            return Ok(());
         }
 
-        self.data[y][x] = state;
+        if self.cells[index] != IntersectionState::Empty {
+            self.remove_stone(index);
+        }
+
+        if state != IntersectionState::Empty {
+            self.place_stone(index, state);
+        }
 
         Ok(())
     }
 
+    /// Returns the intersections next to a position which is on the board, like
+    /// [Board::intersection_neighbours] but without collecting them
+    pub fn adjacent(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+
+        self.neighbour_indices(self.index(x, y))
+            .map(move |neighbour| (neighbour % width, neighbour / width))
+    }
+
+    /// Returns the intersections above, below, to the right and to the left of an intersection,
+    /// regardless of their state.
+    pub fn intersection_neighbours(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> Result<Vec<(usize, usize)>, errors::GoError> {
+        self.check_position(x, y)?;
+
+        Ok(self
+            .neighbour_indices(self.index(x, y))
+            .map(|neighbour| self.position(neighbour))
+            .collect())
+    }
+
     /// Returns the "direct" liberties of an intersection.
     ///
     /// Direct liberties are liberties above, below, to the right and to the left of the intersection.
@@ -110,41 +435,13 @@ impl Board {
         x: usize,
         y: usize,
     ) -> Result<HashSet<(usize, usize)>, errors::GoError> {
-        if x >= self.size() || y >= self.size() {
-            return Err(errors::GoError::InvalidPosition {
-                x,
-                y,
-                size: self.size(),
-            });
-        }
+        self.check_position(x, y)?;
 
-        let mut liberties: HashSet<(usize, usize)> = HashSet::new();
-
-        let possible_relative_liberty_points = vec![(0, -1), (-1, 0), (1, 0), (0, 1)];
-
-        for point in possible_relative_liberty_points {
-            // Note: using checked add to account for edges of the board
-            // If overflow happens, that isn't a valid position on the board
-            let absolute_x_res = x.checked_add_signed(point.0);
-            let absolute_y_res = y.checked_add_signed(point.1);
-
-            if absolute_x_res.is_none() || absolute_y_res.is_none() {
-                continue;
-            }
-
-            let absolute_x = absolute_x_res.unwrap();
-            let absolute_y = absolute_y_res.unwrap();
-
-            let state_res = self.get_intersection(absolute_x, absolute_y);
-
-            if let Some(state) = state_res {
-                if state == IntersectionState::Empty {
-                    liberties.insert((absolute_x, absolute_y));
-                };
-            }
-        }
-
-        return Ok(liberties);
+        Ok(self
+            .neighbour_indices(self.index(x, y))
+            .filter(|&neighbour| self.cells[neighbour] == IntersectionState::Empty)
+            .map(|neighbour| self.position(neighbour))
+            .collect())
     }
 
     /// Returns the true liberties of an intersection.
@@ -158,108 +455,191 @@ impl Board {
         x: usize,
         y: usize,
     ) -> Result<HashSet<(usize, usize)>, errors::GoError> {
-        let home_state_res = self.get_intersection(x, y);
+        let group = self.find_intersections_in_group(x, y)?;
 
-        if let None = home_state_res {
-            return Err(errors::GoError::InvalidPosition {
-                x,
-                y,
-                size: self.size(),
-            });
+        let mut group_liberties: HashSet<(usize, usize)> = HashSet::new();
+
+        for (stone_x, stone_y) in group {
+            for neighbour in self.neighbour_indices(self.index(stone_x, stone_y)) {
+                if self.cells[neighbour] == IntersectionState::Empty {
+                    group_liberties.insert(self.position(neighbour));
+                }
+            }
         }
 
-        let group: HashSet<(usize, usize)> = self.find_intersections_in_group(x, y).unwrap();
+        Ok(group_liberties)
+    }
 
-        let mut group_liberties: HashSet<(usize, usize)> = HashSet::new();
+    /// Returns whether the group of a stone has any liberties, without finding them;
+    ///
+    /// Empty intersections always count as having liberties.
+    pub fn group_has_liberties(&self, x: usize, y: usize) -> Result<bool, errors::GoError> {
+        self.check_position(x, y)?;
 
-        for intersection in group {
-            let intersection_liberties = self
-                .interesection_direct_liberties(intersection.0, intersection.1)
-                .unwrap();
-            for liberty in intersection_liberties.into_iter() {
-                if !group_liberties.contains(&liberty) {
-                    group_liberties.insert(liberty);
-                }
+        let index = self.index(x, y);
+
+        Ok(self.cells[index] == IntersectionState::Empty
+            || self.pseudo_liberties[self.chains[index]] > 0)
+    }
+
+    /// Returns whether a stone may be played on an intersection by the rules every ruleset shares:
+    /// it has to be empty, and the stone needs a liberty or has to capture something.
+    ///
+    /// Ko isn't checked, since it depends on the moves before.
+    pub fn is_legal(&self, x: usize, y: usize, state: IntersectionState) -> bool {
+        if state == IntersectionState::Empty
+            || self.get_intersection(x, y) != Some(IntersectionState::Empty)
+        {
+            return false;
+        }
+
+        let index = self.index(x, y);
+
+        // Most of the time there's a liberty right next to the stone, so check that before counting
+        if self
+            .neighbour_indices(index)
+            .any(|neighbour| self.cells[neighbour] == IntersectionState::Empty)
+        {
+            return true;
+        }
+
+        // Otherwise the stone keeps a liberty through its group, or it captures something
+        self.neighbour_indices(index).any(|neighbour| {
+            let liberties = self.liberties_up_to_two(self.chains[neighbour]);
+
+            if self.cells[neighbour] == state {
+                liberties == 2
+            } else {
+                liberties == 1
+            }
+        })
+    }
+
+    /// Plays a stone if it [is legal](Board::is_legal), capturing the opposing groups it takes the last liberty of.
+    ///
+    /// Returns how many stones were captured, or None if the stone can't be played there.
+    pub fn play_stone(&mut self, x: usize, y: usize, state: IntersectionState) -> Option<usize> {
+        if !self.is_legal(x, y, state) {
+            return None;
+        }
+
+        let index = self.index(x, y);
+        self.place_stone(index, state);
+
+        let mut captured = 0;
+
+        for neighbour in self.neighbour_indices(index) {
+            if self.cells[neighbour] == state.opposite()
+                && self.pseudo_liberties[self.chains[neighbour]] == 0
+            {
+                captured += self.remove_chain(self.chains[neighbour]);
             }
         }
 
-        return Ok(group_liberties);
+        Some(captured)
     }
 
-    /// Find all intersections in a group, with (x, y) being the starting stone.
-    pub fn find_intersections_in_group(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> Result<HashSet<(usize, usize)>, errors::GoError> {
-        let home_state_res = self.get_intersection(x, y);
+    /// Returns the intersection simple ko forbids retaking on, after a stone played at x, y captured the given number of stones.
+    ///
+    /// That's the case when a single stone captured a single stone, and could be captured right back.
+    pub fn ko_point(&self, x: usize, y: usize, captured: usize) -> Option<(usize, usize)> {
+        if captured != 1
+            || self.get_intersection(x, y).unwrap_or_default() == IntersectionState::Empty
+        {
+            return None;
+        }
 
-        if let None = home_state_res {
-            return Err(errors::GoError::InvalidPosition {
-                x,
-                y,
-                size: self.size(),
-            });
+        let index = self.index(x, y);
+        let chain = self.chains[index];
+
+        // A single stone counts each of its liberties once
+        if self.stones[chain] != 1 || self.pseudo_liberties[chain] != 1 {
+            return None;
         }
 
-        let home_state = home_state_res.unwrap();
+        self.neighbour_indices(index)
+            .find(|&neighbour| self.cells[neighbour] == IntersectionState::Empty)
+            .map(|liberty| self.position(liberty))
+    }
+
+    /// Removes the group of a stone from the board, returning how many stones it had
+    pub fn remove_group(&mut self, x: usize, y: usize) -> Result<usize, errors::GoError> {
+        self.check_position(x, y)?;
 
-        let mut group: HashSet<(usize, usize)> = HashSet::new();
-        let mut new_group_stones: HashSet<(usize, usize)> = HashSet::new();
-        new_group_stones.insert((x, y));
+        let index = self.index(x, y);
 
-        while new_group_stones.len() > 0 {
-            self.find_stones_in_group_recurse(home_state, &mut group, &mut new_group_stones);
+        if self.cells[index] == IntersectionState::Empty {
+            return Ok(0);
         }
 
-        return Ok(group);
+        Ok(self.remove_chain(self.chains[index]))
+    }
+
+    /// Returns whether two stones belong to the same group
+    pub fn same_group(&self, first: (usize, usize), second: (usize, usize)) -> bool {
+        let (Some(first_state), Some(second_state)) = (
+            self.get_intersection(first.0, first.1),
+            self.get_intersection(second.0, second.1),
+        ) else {
+            return false;
+        };
+
+        first_state != IntersectionState::Empty
+            && first_state == second_state
+            && self.chains[self.index(first.0, first.1)]
+                == self.chains[self.index(second.0, second.1)]
     }
 
-    /// Private function, recursively called to expand the current group
+    /// Find all intersections in a group, with (x, y) being the starting stone.
     ///
-    /// Finds new intersections connected to the last iteration of new intersections
-    fn find_stones_in_group_recurse(
+    /// For an empty intersection, this is the connected area of empty intersections.
+    pub fn find_intersections_in_group(
         &self,
-        target_state: IntersectionState,
-        group: &mut HashSet<(usize, usize)>,
-        new_group_stones: &mut HashSet<(usize, usize)>,
-    ) {
-        let possible_relative_liberty_points = vec![(0, -1), (-1, 0), (1, 0), (0, 1)];
-
-        for intersection in new_group_stones.clone().into_iter() {
-            for point in possible_relative_liberty_points.clone() {
-                // Note: using checked add to account for edges of the board
-                // If overflow happens, that isn't a valid position on the board
-                let absolute_x_res = intersection.0.checked_add_signed(point.0);
-                let absolute_y_res = intersection.1.checked_add_signed(point.1);
-
-                if absolute_x_res.is_none() || absolute_y_res.is_none() {
-                    continue;
-                }
+        x: usize,
+        y: usize,
+    ) -> Result<HashSet<(usize, usize)>, errors::GoError> {
+        self.check_position(x, y)?;
 
-                let absolute_x = absolute_x_res.unwrap();
-                let absolute_y = absolute_y_res.unwrap();
+        let start = self.index(x, y);
 
-                if group.contains(&(absolute_x, absolute_y))
-                    || new_group_stones.contains(&(absolute_x, absolute_y))
-                {
-                    continue;
-                }
+        if self.cells[start] != IntersectionState::Empty {
+            return Ok(self
+                .chain_stones(self.chains[start])
+                .into_iter()
+                .map(|stone| self.position(stone))
+                .collect());
+        }
+
+        // Empty areas aren't kept track of, so search for them
+        let mut visited = vec![false; self.cells.len()];
+        let mut to_visit = vec![start];
+        let mut group = HashSet::new();
+        visited[start] = true;
 
-                let state_res = self.get_intersection(absolute_x, absolute_y);
+        while let Some(index) = to_visit.pop() {
+            group.insert(self.position(index));
 
-                if let Some(state) = state_res {
-                    if state == target_state {
-                        new_group_stones.insert((absolute_x, absolute_y));
-                    };
+            for neighbour in self.neighbour_indices(index) {
+                if !visited[neighbour] && self.cells[neighbour] == IntersectionState::Empty {
+                    visited[neighbour] = true;
+                    to_visit.push(neighbour);
                 }
             }
-            new_group_stones.remove(&intersection);
-            group.insert(intersection);
         }
+
+        Ok(group)
+    }
+}
+
+impl PartialEq for Board {
+    /// Boards are equal when their stones are; how chains are labelled doesn't matter
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.cells == other.cells
     }
 }
 
+impl Eq for Board {}
+
 impl Default for Board {
     fn default() -> Self {
         Self::new(9)
@@ -270,17 +650,11 @@ impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut output = String::new();
 
-        let mut display_board: Vec<Vec<String>> = Vec::new();
-
-        for row in self.data.clone() {
-            let mut display_row: Vec<String> = Vec::new();
-            for intersection in row {
-                display_row.push(format!("{}", intersection))
-            }
-            display_board.push(display_row);
-        }
-
-        for display_row in display_board {
+        for row in self.get_data() {
+            let display_row: Vec<String> = row
+                .into_iter()
+                .map(|intersection| format!("{}", intersection))
+                .collect();
             output.push_str(&format!("{:?}\n", display_row));
         }
 
@@ -291,8 +665,40 @@ impl fmt::Debug for Board {
 #[cfg(test)]
 mod tests {
 
+    use super::{Board, IntersectionState};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
     use std::collections::HashSet;
 
+    /// Finds a group by searching the rows, the way the board did before keeping track of chains
+    fn search_group(
+        data: &[Vec<IntersectionState>],
+        x: usize,
+        y: usize,
+    ) -> HashSet<(usize, usize)> {
+        let size = data.len();
+        let state = data[y][x];
+        let mut group = HashSet::from([(x, y)]);
+        let mut to_visit = vec![(x, y)];
+
+        while let Some((x, y)) = to_visit.pop() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+
+            for (nx, ny) in neighbours {
+                if nx < size && ny < size && data[ny][nx] == state && group.insert((nx, ny)) {
+                    to_visit.push((nx, ny));
+                }
+            }
+        }
+
+        group
+    }
+
     #[test]
     pub fn direct_liberites() {
         let board = super::Board::new(9);
@@ -302,7 +708,7 @@ mod tests {
         assert_eq!(liberties.len(), 4);
         assert_eq!(
             liberties,
-            HashSet::from_iter(vec![(4, 3), (3, 4), (5, 4), (4, 5)].iter().cloned())
+            HashSet::from_iter([(4, 3), (3, 4), (5, 4), (4, 5)].iter().cloned())
         );
 
         let edge_liberties = board.interesection_direct_liberties(0, 0).unwrap();
@@ -310,7 +716,7 @@ mod tests {
         assert_eq!(edge_liberties.len(), 2);
         assert_eq!(
             edge_liberties,
-            HashSet::from_iter(vec![(0, 1), (1, 0)].iter().cloned())
+            HashSet::from_iter([(0, 1), (1, 0)].iter().cloned())
         );
     }
 
@@ -434,4 +840,148 @@ mod tests {
 
         assert_eq!(liberties.len(), 7);
     }
+
+    #[test]
+    pub fn chains_follow_edits() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let mut board = Board::new(7);
+        let states = [
+            IntersectionState::Empty,
+            IntersectionState::Black,
+            IntersectionState::White,
+        ];
+
+        for _ in 0..1000 {
+            let (x, y) = (rng.gen_range(0..7), rng.gen_range(0..7));
+            let state = states[rng.gen_range(0..3)];
+            board.set_intersection(x, y, state).unwrap();
+
+            let data = board.get_data();
+
+            for y in 0..7 {
+                for x in 0..7 {
+                    let group = search_group(&data, x, y);
+                    assert_eq!(board.find_intersections_in_group(x, y).unwrap(), group);
+
+                    let has_liberties = data[y][x] == IntersectionState::Empty
+                        || group.iter().any(|&(gx, gy)| {
+                            board
+                                .intersection_neighbours(gx, gy)
+                                .unwrap()
+                                .iter()
+                                .any(|&(nx, ny)| data[ny][nx] == IntersectionState::Empty)
+                        });
+                    assert_eq!(board.group_has_liberties(x, y), Ok(has_liberties));
+                }
+            }
+        }
+
+        let mut copy = Board::new(3);
+        copy.set_data(board.get_data());
+        assert_eq!(copy, board);
+
+        board.remove_group(0, 0).unwrap();
+        assert_eq!(board.get_intersection(0, 0), Some(IntersectionState::Empty));
+    }
+
+    #[test]
+    pub fn rectangular_board() {
+        let mut board = Board::new_rect(7, 3);
+
+        assert_eq!((board.width(), board.height()), (7, 3));
+        assert!(!board.is_square());
+        assert_eq!(
+            board.intersection_neighbours(6, 2).unwrap(),
+            vec![(6, 1), (5, 2)]
+        );
+        assert_eq!(
+            board.set_intersection(3, 3, IntersectionState::Black),
+            Err(crate::errors::GoError::InvalidPosition {
+                x: 3,
+                y: 3,
+                width: 7,
+                height: 3
+            })
+        );
+
+        for x in 0..7 {
+            board
+                .set_intersection(x, 1, IntersectionState::Black)
+                .unwrap();
+        }
+
+        assert_eq!(board.find_intersections_in_group(0, 1).unwrap().len(), 7);
+        assert_eq!(board.find_true_liberties(6, 1).unwrap().len(), 14);
+        assert_eq!(board.find_intersections_in_group(6, 0).unwrap().len(), 7);
+        assert_eq!(board.get_data().len(), 3);
+        assert_eq!(board.get_data()[0].len(), 7);
+    }
+
+    #[test]
+    pub fn play_stone() {
+        use IntersectionState::{Black, White};
+
+        let mut board = Board::new(5);
+
+        // Capturing in the corner, after which white can't play back into it
+        assert_eq!(board.play_stone(0, 0, White), Some(0));
+        assert_eq!(board.play_stone(1, 0, Black), Some(0));
+        assert_eq!(board.play_stone(0, 1, Black), Some(1));
+        assert_eq!(board.get_intersection(0, 0), Some(IntersectionState::Empty));
+        assert!(!board.is_legal(0, 0, White));
+        assert_eq!(board.play_stone(0, 0, White), None);
+        assert_eq!(board.play_stone(1, 0, White), None);
+        assert_eq!(board.play_stone(9, 9, White), None);
+        assert_eq!(board.ko_point(0, 1, 1), None);
+
+        // A group filling its own last liberty is suicide, but not when it captures
+        for (x, y) in [(2, 0), (1, 1), (0, 2)] {
+            board.play_stone(x, y, White).unwrap();
+        }
+        assert!(board.is_legal(0, 0, White));
+        assert_eq!(board.play_stone(0, 0, White), Some(2));
+        assert!(!board.is_legal(1, 0, Black));
+
+        // A ko: black takes at (2, 1) and could be taken right back at (1, 1)
+        let mut board = Board::new(5);
+        for (x, y) in [(1, 0), (0, 1), (1, 2)] {
+            board.play_stone(x, y, Black).unwrap();
+        }
+        for (x, y) in [(2, 0), (3, 1), (2, 2), (1, 1)] {
+            board.play_stone(x, y, White).unwrap();
+        }
+
+        assert_eq!(board.play_stone(2, 1, Black), Some(1));
+        assert_eq!(board.ko_point(2, 1, 1), Some((1, 1)));
+        assert!(board.is_legal(1, 1, White));
+    }
+
+    #[test]
+    pub fn huge_board() {
+        // Points past 65535 would collide with the first ones if chains used 16 bit indices
+        let mut board = Board::new_rect(300, 300);
+        let far = (65536 % 300, 65536 / 300);
+
+        board
+            .set_intersection(0, 0, IntersectionState::Black)
+            .unwrap();
+        board
+            .set_intersection(far.0, far.1, IntersectionState::Black)
+            .unwrap();
+
+        assert!(!board.same_group((0, 0), far));
+        assert_eq!(board.find_true_liberties(0, 0).unwrap().len(), 2);
+        assert_eq!(board.find_true_liberties(far.0, far.1).unwrap().len(), 4);
+
+        for (x, y, state) in [
+            (299, 299, IntersectionState::Black),
+            (299, 298, IntersectionState::White),
+            (298, 299, IntersectionState::White),
+        ] {
+            board.set_intersection(x, y, state).unwrap();
+        }
+        assert!(!board.group_has_liberties(299, 299).unwrap());
+        assert_eq!(board.remove_group(299, 299).unwrap(), 1);
+        assert!(board.group_has_liberties(0, 0).unwrap());
+    }
 }
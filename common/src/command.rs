@@ -0,0 +1,597 @@
+use crate::board::IntersectionState;
+use crate::coordinates;
+use crate::errors::GoError;
+use crate::gamecommand::GameCommand;
+use crate::gtp::parse_color;
+use crate::ruleset::{KoRule, Ruleset, ScoringRule};
+
+/// Describes a command of the local client for the help command
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CommandHelp {
+    /// The name of the command, followed by its aliases
+    pub names: &'static [&'static str],
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+impl CommandHelp {
+    /// Returns the name commands are matched by
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+}
+
+impl std::fmt::Display for CommandHelp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<24} {}", self.usage, self.description)?;
+
+        if self.names.len() > 1 {
+            write!(f, " (also {})", self.names[1..].join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// All commands of the local client, in the order help lists them
+pub const COMMANDS: &[CommandHelp] = &[
+    CommandHelp {
+        names: &["b", "black"],
+        usage: "b {position}",
+        description: "Places a black stone, like b D4",
+    },
+    CommandHelp {
+        names: &["w", "white"],
+        usage: "w {position}",
+        description: "Places a white stone, like w Q16",
+    },
+    CommandHelp {
+        names: &["pass", "p"],
+        usage: "pass",
+        description: "Passes the turn",
+    },
+    CommandHelp {
+        names: &["resign"],
+        usage: "resign [b|w]",
+        description: "Resigns, by default for the player to move",
+    },
+    CommandHelp {
+        names: &["undo", "u"],
+        usage: "undo",
+        description: "Goes back to before the last move",
+    },
+    CommandHelp {
+        names: &["redo", "r"],
+        usage: "redo",
+        description: "Redoes the next move of the current line",
+    },
+    CommandHelp {
+        names: &["first"],
+        usage: "first",
+        description: "Goes to the start of the game",
+    },
+    CommandHelp {
+        names: &["last"],
+        usage: "last",
+        description: "Goes to the end of the current line",
+    },
+    CommandHelp {
+        names: &["goto"],
+        usage: "goto {move}",
+        description: "Goes to the position after the given move",
+    },
+    CommandHelp {
+        names: &["next", "n"],
+        usage: "next [variation]",
+        description: "Steps forward, into the main line or the given variation",
+    },
+    CommandHelp {
+        names: &["back", "prev"],
+        usage: "back",
+        description: "Steps back",
+    },
+    CommandHelp {
+        names: &["variations", "vars"],
+        usage: "variations",
+        description: "Lists the variations after this position",
+    },
+    CommandHelp {
+        names: &["variation", "var"],
+        usage: "variation {n}",
+        description: "Switches to another variation of the last move",
+    },
+    CommandHelp {
+        names: &["promote"],
+        usage: "promote",
+        description: "Makes the current variation the main line",
+    },
+    CommandHelp {
+        names: &["info", "in"],
+        usage: "info {position}",
+        description: "Prints the liberties of a stone",
+    },
+    CommandHelp {
+        names: &["score"],
+        usage: "score [area|territory]",
+        description: "Prints the score of the board",
+    },
+    CommandHelp {
+        names: &["mark"],
+        usage: "mark {position}",
+        description: "Marks a group dead or alive after both players passed",
+    },
+    CommandHelp {
+        names: &["accept"],
+        usage: "accept {b|w}",
+        description: "Accepts the dead stone marking for a player",
+    },
+    CommandHelp {
+        names: &["resume"],
+        usage: "resume",
+        description: "Continues a game which ended by passing",
+    },
+    CommandHelp {
+        names: &["rules"],
+        usage: "rules {name}",
+        description: "Switches to a ruleset, like japanese or chinese",
+    },
+    CommandHelp {
+        names: &["ko"],
+        usage: "ko {rule}",
+        description: "Switches the ko rule: none, simple, psk or ssk",
+    },
+    CommandHelp {
+        names: &["komi"],
+        usage: "komi {points}",
+        description: "Sets the komi",
+    },
+    CommandHelp {
+        names: &["edit"],
+        usage: "edit",
+        description: "Toggles edit mode, to place stones freely",
+    },
+    CommandHelp {
+        names: &["turn"],
+        usage: "turn {b|w}",
+        description: "Sets whose turn it is",
+    },
+    CommandHelp {
+        names: &["comment"],
+        usage: "comment [text]",
+        description: "Comments the current position",
+    },
+    CommandHelp {
+        names: &["name"],
+        usage: "name {b|w} {name}",
+        description: "Sets a player's name",
+    },
+    CommandHelp {
+        names: &["save"],
+        usage: "save {file}",
+        description: "Saves the game as an SGF file",
+    },
+    CommandHelp {
+        names: &["load"],
+        usage: "load {file}",
+        description: "Loads an SGF file to step through",
+    },
+    CommandHelp {
+        names: &["help", "h", "?"],
+        usage: "help [command]",
+        description: "Lists the commands, or explains one",
+    },
+    CommandHelp {
+        names: &["exit", "quit", "q", "end"],
+        usage: "exit",
+        description: "Closes the program",
+    },
+];
+
+/// Finds the help of a command by its name or one of its aliases, ignoring case
+pub fn find_command(name: &str) -> Option<&'static CommandHelp> {
+    let name = name.to_lowercase();
+
+    COMMANDS
+        .iter()
+        .find(|command| command.names.contains(&name.as_str()))
+}
+
+/// A command typed into the local client
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    /// Commands which change the game, like placing stones or undoing
+    Game(GameCommand),
+    /// Resigns for the given player, or the player to move
+    Resign(Option<IntersectionState>),
+    /// Steps into the given variation of the next move, 0 being the main line
+    Next(usize),
+    Back,
+    Variations,
+    /// Switches to the given variation of the last move, counted from 0
+    Variation(usize),
+    Promote,
+    /// Prints the liberties of the stone at (0, 1)
+    Info(usize, usize),
+    /// Prints the score, by default with the scoring rule of the ruleset
+    Score(Option<ScoringRule>),
+    Mark(usize, usize),
+    Accept(IntersectionState),
+    Rules(Ruleset),
+    Ko(KoRule),
+    Komi(f32),
+    Edit,
+    Turn(IntersectionState),
+    Comment(String),
+    Name(IntersectionState, String),
+    Save(String),
+    Load(String),
+    /// Lists all commands, or explains one
+    Help(Option<&'static CommandHelp>),
+    Exit,
+}
+
+/// A word of a command, along with where it starts in the input
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub start: usize,
+}
+
+/// Splits a command into its words, which are separated by spaces or the semicolons of the legacy format
+pub fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (index, character) in input.char_indices() {
+        let separator = character.is_whitespace() || character == ';';
+
+        match (start, separator) {
+            (None, false) => start = Some(index),
+            (Some(begin), true) => {
+                tokens.push(Token {
+                    text: &input[begin..index],
+                    start: begin,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(begin) = start {
+        tokens.push(Token {
+            text: &input[begin..],
+            start: begin,
+        });
+    }
+
+    tokens
+}
+
+impl Command {
+    /// Parses a command for a board of the given size.
+    ///
+    /// Positions are written in standard notation, like D4, or in the legacy format, like D;4.
+    pub fn parse(input: &str, width: usize, height: usize) -> Result<Command, GoError> {
+        let tokens = tokenize(input);
+
+        let Some(first) = tokens.first() else {
+            return Err(GoError::UnknownCommand {
+                name: String::new(),
+            });
+        };
+
+        let help = find_command(first.text).ok_or_else(|| GoError::UnknownCommand {
+            name: first.text.to_string(),
+        })?;
+
+        let arguments = Arguments {
+            input,
+            tokens: &tokens[1..],
+            usage: help.usage,
+        };
+
+        let command = match help.name() {
+            "b" | "w" => {
+                let color = parse_color(help.name()).unwrap_or_default();
+                let (x, y) = arguments.position(width, height)?;
+                Command::Game(GameCommand::Set(x, y, color))
+            }
+            "pass" => arguments.none(Command::Game(GameCommand::Pass))?,
+            "resign" => match arguments.tokens {
+                [] => Command::Resign(None),
+                _ => Command::Resign(Some(arguments.color()?)),
+            },
+            "undo" => arguments.none(Command::Game(GameCommand::Undo))?,
+            "redo" => arguments.none(Command::Game(GameCommand::Redo))?,
+            "first" => arguments.none(Command::Game(GameCommand::First))?,
+            "last" => arguments.none(Command::Game(GameCommand::Last))?,
+            "goto" => Command::Game(GameCommand::GoTo(arguments.number()?)),
+            "next" => match arguments.tokens {
+                [] => Command::Next(0),
+                _ => Command::Next(arguments.counted_from_one()?),
+            },
+            "back" => arguments.none(Command::Back)?,
+            "variations" => arguments.none(Command::Variations)?,
+            "variation" => Command::Variation(arguments.counted_from_one()?),
+            "promote" => arguments.none(Command::Promote)?,
+            "info" => {
+                let (x, y) = arguments.position(width, height)?;
+                Command::Info(x, y)
+            }
+            "score" => match arguments.single_word()? {
+                None => Command::Score(None),
+                Some(rule) => Command::Score(Some(match rule.to_lowercase().as_str() {
+                    "area" => ScoringRule::Area,
+                    "territory" => ScoringRule::Territory,
+                    _ => {
+                        return Err(GoError::UnknownRule {
+                            name: rule.to_string(),
+                        })
+                    }
+                })),
+            },
+            "mark" => {
+                let (x, y) = arguments.position(width, height)?;
+                Command::Mark(x, y)
+            }
+            "accept" => Command::Accept(arguments.color()?),
+            "resume" => arguments.none(Command::Game(GameCommand::Resume))?,
+            "rules" => {
+                let name = arguments.text(0)?;
+                Command::Rules(
+                    Ruleset::from_name(name).ok_or_else(|| GoError::UnknownRule {
+                        name: name.to_string(),
+                    })?,
+                )
+            }
+            "ko" => {
+                let name = arguments.text(0)?;
+                Command::Ko(KoRule::from_name(name).ok_or_else(|| GoError::UnknownRule {
+                    name: name.to_string(),
+                })?)
+            }
+            "komi" => {
+                let points = arguments.text(0)?;
+                let komi = points
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|komi| komi.is_finite())
+                    .ok_or_else(|| arguments.invalid(points))?;
+                Command::Komi(komi)
+            }
+            "edit" => arguments.none(Command::Edit)?,
+            "turn" => Command::Turn(arguments.color()?),
+            "comment" => Command::Comment(arguments.rest(0).to_string()),
+            "name" => {
+                let color = arguments.color_at(0)?;
+                Command::Name(color, arguments.text(1)?.to_string())
+            }
+            "save" => Command::Save(arguments.text(0)?.to_string()),
+            "load" => Command::Load(arguments.text(0)?.to_string()),
+            "help" => match arguments.single_word()? {
+                None => Command::Help(None),
+                Some(name) => Command::Help(Some(find_command(name).ok_or_else(|| {
+                    GoError::UnknownCommand {
+                        name: name.to_string(),
+                    }
+                })?)),
+            },
+            "exit" => arguments.none(Command::Exit)?,
+            name => unreachable!("Command {} is listed but not parsed", name),
+        };
+
+        Ok(command)
+    }
+}
+
+/// The arguments following the name of a command
+struct Arguments<'a> {
+    input: &'a str,
+    tokens: &'a [Token<'a>],
+    usage: &'static str,
+}
+
+impl Arguments<'_> {
+    fn missing(&self) -> GoError {
+        GoError::MissingArgument { usage: self.usage }
+    }
+
+    fn invalid(&self, argument: &str) -> GoError {
+        GoError::InvalidArgument {
+            argument: argument.to_string(),
+            usage: self.usage,
+        }
+    }
+
+    /// Checks that there are no more arguments than the given number
+    fn at_most(&self, count: usize) -> Result<(), GoError> {
+        match self.tokens.get(count) {
+            Some(extra) => Err(GoError::UnexpectedArgument {
+                argument: extra.text.to_string(),
+                usage: self.usage,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the command, if it was given without arguments
+    fn none(&self, command: Command) -> Result<Command, GoError> {
+        self.at_most(0)?;
+        Ok(command)
+    }
+
+    /// Returns the only argument, if there is one
+    fn single_word(&self) -> Result<Option<&str>, GoError> {
+        self.at_most(1)?;
+        Ok(self.tokens.first().map(|token| token.text))
+    }
+
+    /// Returns the text from the argument with the given index to the end of the input
+    fn rest(&self, index: usize) -> &str {
+        self.tokens
+            .get(index)
+            .map_or("", |token| self.input[token.start..].trim())
+    }
+
+    /// Like rest, but the text can't be empty
+    fn text(&self, index: usize) -> Result<&str, GoError> {
+        match self.rest(index) {
+            "" => Err(self.missing()),
+            text => Ok(text),
+        }
+    }
+
+    fn color_at(&self, index: usize) -> Result<IntersectionState, GoError> {
+        let token = self.tokens.get(index).ok_or_else(|| self.missing())?;
+        parse_color(token.text).ok_or_else(|| self.invalid(token.text))
+    }
+
+    /// Parses a colour, which has to be the only argument
+    fn color(&self) -> Result<IntersectionState, GoError> {
+        self.at_most(1)?;
+        self.color_at(0)
+    }
+
+    fn number(&self) -> Result<usize, GoError> {
+        let number = self.single_word()?.ok_or_else(|| self.missing())?;
+        number.parse::<usize>().map_err(|_| self.invalid(number))
+    }
+
+    /// Parses a number which is shown counted from 1, like variations, to one counted from 0
+    fn counted_from_one(&self) -> Result<usize, GoError> {
+        let number = self.number()?;
+        number
+            .checked_sub(1)
+            .ok_or_else(|| self.invalid(&number.to_string()))
+    }
+
+    /// Parses a position in standard notation, or in the legacy format as a column and a row
+    fn position(&self, width: usize, height: usize) -> Result<(usize, usize), GoError> {
+        match self.tokens {
+            [] => Err(self.missing()),
+            [position] => coordinates::parse_position(position.text, width, height)
+                .ok_or_else(|| self.invalid(position.text)),
+            [x, y] => coordinates::parse_legacy_position(x.text, y.text)
+                .filter(|&(x, y)| x < width && y < height)
+                .ok_or_else(|| self.invalid(&format!("{};{}", x.text, y.text))),
+            [_, _, extra, ..] => Err(GoError::UnexpectedArgument {
+                argument: extra.text.to_string(),
+                usage: self.usage,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+    use crate::board::IntersectionState::*;
+    use crate::errors::GoError;
+    use crate::gamecommand::GameCommand;
+    use crate::ruleset::KoRule;
+
+    fn parse(input: &str) -> Result<Command, GoError> {
+        Command::parse(input, 19, 19)
+    }
+
+    #[test]
+    pub fn tokenize() {
+        let words: Vec<&str> = super::tokenize(" name;b; Honinbo  Shusaku")
+            .iter()
+            .map(|token| token.text)
+            .collect();
+
+        assert_eq!(words, ["name", "b", "Honinbo", "Shusaku"]);
+        assert!(super::tokenize(" ; ").is_empty());
+    }
+
+    #[test]
+    pub fn moves() {
+        let black_d4 = Ok(Command::Game(GameCommand::Set(3, 15, Black)));
+
+        assert_eq!(parse("b D4"), black_d4);
+        assert_eq!(parse("Black d4"), black_d4);
+        assert_eq!(parse("b;d;16"), black_d4);
+        assert_eq!(
+            parse("white q16"),
+            Ok(Command::Game(GameCommand::Set(15, 3, White)))
+        );
+        assert_eq!(parse("p"), Ok(Command::Game(GameCommand::Pass)));
+        assert_eq!(parse("resign"), Ok(Command::Resign(None)));
+        assert_eq!(parse("resign;w"), Ok(Command::Resign(Some(White))));
+    }
+
+    #[test]
+    pub fn arguments() {
+        assert_eq!(parse("in D4"), Ok(Command::Info(3, 15)));
+        assert_eq!(parse("next"), Ok(Command::Next(0)));
+        assert_eq!(parse("n 2"), Ok(Command::Next(1)));
+        assert_eq!(parse("goto 12"), Ok(Command::Game(GameCommand::GoTo(12))));
+        assert_eq!(parse("ko psk"), Ok(Command::Ko(KoRule::PositionalSuperko)));
+        assert_eq!(parse("komi 6.5"), Ok(Command::Komi(6.5)));
+        assert_eq!(
+            parse("name;b;Honinbo Shusaku"),
+            Ok(Command::Name(Black, String::from("Honinbo Shusaku")))
+        );
+        assert_eq!(
+            parse("save My Game.sgf"),
+            Ok(Command::Save(String::from("My Game.sgf")))
+        );
+        assert_eq!(parse("comment"), Ok(Command::Comment(String::new())));
+        assert_eq!(
+            parse("help mark"),
+            Ok(Command::Help(super::find_command("mark")))
+        );
+    }
+
+    #[test]
+    pub fn errors() {
+        // Words containing the name of a command aren't that command
+        assert_eq!(
+            parse("join"),
+            Err(GoError::UnknownCommand {
+                name: String::from("join")
+            })
+        );
+        assert_eq!(
+            parse("info"),
+            Err(GoError::MissingArgument {
+                usage: "info {position}"
+            })
+        );
+        assert_eq!(
+            parse("b Z99"),
+            Err(GoError::InvalidArgument {
+                argument: String::from("Z99"),
+                usage: "b {position}"
+            })
+        );
+        assert_eq!(
+            parse("undo 2"),
+            Err(GoError::UnexpectedArgument {
+                argument: String::from("2"),
+                usage: "undo"
+            })
+        );
+        assert!(parse("variation 0").is_err());
+        assert!(parse("komi lots").is_err());
+        assert!(parse("turn x").is_err());
+        assert!(parse("rules unknown").is_err());
+        assert!(parse("help unknown").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    pub fn every_command_parses() {
+        for command in super::COMMANDS {
+            // A listed command without a parser would panic here
+            let _ = parse(command.name());
+
+            assert_eq!(
+                super::find_command(&command.name().to_uppercase()),
+                Some(command)
+            );
+        }
+    }
+}
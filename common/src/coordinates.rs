@@ -0,0 +1,304 @@
+use crate::errors::GoError;
+
+/// The column letters of standard notation, which skip I so it isn't mistaken for J or 1
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// The column letters of the legacy format, which still include I
+const LEGACY_COLUMNS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The letters of SGF points, a - z for 0 - 25 and A - Z for 26 - 51, as in FF[4]
+const SGF_LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Names a column with the given letters; After the last one come two letter names, like spreadsheet columns
+fn name_column(letters: &str, x: usize) -> String {
+    let base = letters.len();
+    let mut name = Vec::new();
+    let mut rest = x + 1;
+
+    while rest > 0 {
+        rest -= 1;
+        name.push(letters.as_bytes()[rest % base] as char);
+        rest /= base;
+    }
+
+    name.iter().rev().collect()
+}
+
+/// Parses the name of a column written with the given letters, ignoring case
+fn parse_column_with(letters: &str, name: &str) -> Option<usize> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut x: usize = 0;
+
+    for character in name.chars() {
+        let digit = letters.find(character.to_ascii_uppercase())?;
+        x = x.checked_mul(letters.len())?.checked_add(digit + 1)?;
+    }
+
+    Some(x - 1)
+}
+
+/// Returns the name of a column in standard notation, like the board labels show it.
+///
+/// After Z come two letter names, AA, AB and so on, so boards wider than 25 can be labelled too.
+pub fn column_name(x: usize) -> String {
+    name_column(COLUMNS, x)
+}
+
+/// Parses the name of a column in standard notation, ignoring case; The reverse of [column_name]
+pub fn parse_column(name: &str) -> Option<usize> {
+    parse_column_with(COLUMNS, name)
+}
+
+/// Returns the name of a row, counted from 1 at the bottom of a board of the given height.
+///
+/// Panics if the row isn't on the board, since it has no name then.
+pub fn row_name(y: usize, height: usize) -> String {
+    assert!(y < height, "row {} is off a board {} high", y, height);
+
+    (height - y).to_string()
+}
+
+/// Parses the name of a row on a board of the given height; The reverse of [row_name]
+pub fn parse_row(name: &str, height: usize) -> Option<usize> {
+    let row = name.trim().parse::<usize>().ok()?;
+
+    if row == 0 || row > height {
+        return None;
+    }
+
+    Some(height - row)
+}
+
+/// Returns the name of a position in standard notation, like "D4" or "Q16".
+///
+/// Positions are counted from the top left corner, while standard notation counts rows from the bottom,
+/// as Go books, GTP and most Go software do. Like [row_name], this panics for positions off the board.
+pub fn position_name(x: usize, y: usize, height: usize) -> String {
+    format!("{}{}", column_name(x), row_name(y, height))
+}
+
+/// Names a position which may be off the board, in standard notation extended past its edges;
+/// Rows below the bottom one count on to 0 and then negative numbers, like "D0" or "D-2".
+pub fn off_board_position_name(x: usize, y: usize, height: usize) -> String {
+    format!("{}{}", column_name(x), height as i64 - y as i64)
+}
+
+/// Parses a position in standard notation, like "D4" or "q16", which has to be on the board
+pub fn parse_position(name: &str, width: usize, height: usize) -> Option<(usize, usize)> {
+    let name = name.trim();
+    let split = name.find(|c: char| c.is_ascii_digit())?;
+
+    let x = parse_column(&name[..split]).filter(|&x| x < width)?;
+    let y = parse_row(&name[split..], height)?;
+
+    Some((x, y))
+}
+
+/// Parses a GTP vertex, like "D4" or "pass", into a position; pass is None.
+///
+/// Vertices are written in standard notation, so GTP counts rows from the bottom of the board.
+pub fn parse_vertex(vertex: &str, size: usize) -> Result<Option<(usize, usize)>, GoError> {
+    if vertex.eq_ignore_ascii_case("pass") {
+        return Ok(None);
+    }
+
+    parse_position(vertex, size, size)
+        .map(Some)
+        .ok_or_else(|| GoError::InvalidVertex {
+            vertex: vertex.to_string(),
+        })
+}
+
+/// Formats a position as a GTP vertex, with None being a pass
+pub fn format_vertex(position: Option<(usize, usize)>, size: usize) -> String {
+    match position {
+        Some((x, y)) => position_name(x, y, size),
+        None => String::from("pass"),
+    }
+}
+
+/// Converts a position to an SGF point, such as "dd"
+///
+/// SGF points only name columns and rows up to 51, so positions past them are None.
+pub fn to_sgf_point(x: usize, y: usize) -> Option<String> {
+    let to_char = |value: usize| {
+        SGF_LETTERS
+            .as_bytes()
+            .get(value)
+            .map(|&letter| letter as char)
+    };
+
+    Some(format!("{}{}", to_char(x)?, to_char(y)?))
+}
+
+/// Converts an SGF point, such as "dd", to a position
+pub fn from_sgf_point(point: &str) -> Option<(usize, usize)> {
+    let from_char = |character: char| match character {
+        'a'..='z' => Some(character as usize - 'a' as usize),
+        'A'..='Z' => Some(character as usize - 'A' as usize + 26),
+        _ => None,
+    };
+
+    let mut chars = point.chars();
+    let x = from_char(chars.next()?)?;
+    let y = from_char(chars.next()?)?;
+
+    if chars.next().is_some() {
+        return None;
+    }
+
+    Some((x, y))
+}
+
+/// Returns the name of a position in the legacy format, like "D;4"
+///
+/// Its columns include I and its rows count from 1 at the top, as gorst's first board labels did.
+pub fn legacy_position_name(x: usize, y: usize) -> String {
+    format!("{};{}", name_column(LEGACY_COLUMNS, x), y + 1)
+}
+
+/// Parses a position in the legacy format, given as its column and row, like "D" and "4"
+pub fn parse_legacy_position(x: &str, y: &str) -> Option<(usize, usize)> {
+    let x = parse_column_with(LEGACY_COLUMNS, x.trim())?;
+    let y = y.trim().parse::<usize>().ok()?.checked_sub(1)?;
+
+    Some((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::GoError;
+
+    #[test]
+    pub fn columns() {
+        for (x, name) in [
+            (0, "A"),
+            (7, "H"),
+            (8, "J"),
+            (24, "Z"),
+            (25, "AA"),
+            (33, "AJ"),
+            (36, "AM"),
+            (650, "AAA"),
+        ] {
+            assert_eq!(super::column_name(x), name);
+            assert_eq!(super::parse_column(name), Some(x));
+        }
+
+        assert_eq!(super::parse_column("am"), Some(36));
+        assert_eq!(super::parse_column("I"), None);
+        assert_eq!(super::parse_column(""), None);
+        assert_eq!(super::parse_column("A1"), None);
+    }
+
+    #[test]
+    pub fn standard_notation() {
+        assert_eq!(super::position_name(3, 15, 19), "D4");
+        assert_eq!(super::position_name(15, 3, 19), "Q16");
+        assert_eq!(super::position_name(36, 0, 37), "AM37");
+
+        assert_eq!(super::parse_position("D4", 19, 19), Some((3, 15)));
+        assert_eq!(super::parse_position(" q16 ", 19, 19), Some((15, 3)));
+        assert_eq!(super::parse_position("am37", 37, 37), Some((36, 0)));
+
+        // Off the board, or not a position at all
+        assert_eq!(super::parse_position("U1", 19, 19), None);
+        assert_eq!(super::parse_position("A20", 19, 19), None);
+        assert_eq!(super::parse_position("A0", 19, 19), None);
+        assert_eq!(super::parse_position("I5", 19, 19), None);
+        assert_eq!(super::parse_position("D", 19, 19), None);
+        assert_eq!(super::parse_position("4", 19, 19), None);
+
+        // Rows count from the bottom of rectangular boards too
+        assert_eq!(super::parse_position("N1", 13, 9), Some((12, 8)));
+        assert_eq!(super::parse_position("N10", 13, 9), None);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn row_off_the_board() {
+        super::row_name(19, 19);
+    }
+
+    #[test]
+    pub fn off_the_board() {
+        assert_eq!(super::off_board_position_name(3, 15, 19), "D4");
+        assert_eq!(super::off_board_position_name(19, 0, 19), "U19");
+        assert_eq!(super::off_board_position_name(0, 21, 19), "A-2");
+
+        let error = GoError::InvalidPosition {
+            x: 9,
+            y: 9,
+            width: 9,
+            height: 9,
+        };
+        assert_eq!(error.to_string(), "Position K0 is off the 9x9 board");
+
+        // Legacy positions are checked against the board like standard ones
+        let command = crate::gamecommand::GameCommand::try_from_string(String::from("b;K;4"), 9, 9);
+        assert_eq!(command, None);
+    }
+
+    #[test]
+    pub fn vertices() {
+        assert_eq!(super::parse_vertex("A1", 9), Ok(Some((0, 8))));
+        assert_eq!(super::parse_vertex("j9", 9), Ok(Some((8, 0))));
+        assert_eq!(super::parse_vertex("PASS", 9), Ok(None));
+        assert_eq!(
+            super::parse_vertex("I5", 9),
+            Err(GoError::InvalidVertex {
+                vertex: String::from("I5")
+            })
+        );
+        assert!(super::parse_vertex("K1", 9).is_err());
+        assert!(super::parse_vertex("A10", 9).is_err());
+
+        assert_eq!(super::format_vertex(Some((8, 0)), 9), "J9");
+        assert_eq!(super::format_vertex(None, 9), "pass");
+    }
+
+    #[test]
+    pub fn sgf_points() {
+        assert_eq!(super::to_sgf_point(3, 3).unwrap(), "dd");
+        assert_eq!(super::to_sgf_point(0, 18).unwrap(), "as");
+        assert_eq!(super::to_sgf_point(26, 51).unwrap(), "AZ");
+        assert_eq!(super::to_sgf_point(52, 0), None);
+        assert_eq!(super::to_sgf_point(0, 53), None);
+        assert_eq!(super::to_sgf_point(239, 239), None);
+
+        assert_eq!(super::from_sgf_point("as"), Some((0, 18)));
+        assert_eq!(super::from_sgf_point("AZ"), Some((26, 51)));
+        assert_eq!(super::from_sgf_point("a"), None);
+        assert_eq!(super::from_sgf_point("abc"), None);
+    }
+
+    #[test]
+    pub fn legacy_format() {
+        assert_eq!(super::legacy_position_name(8, 3), "I;4");
+        assert_eq!(super::parse_legacy_position("i", "4"), Some((8, 3)));
+        assert_eq!(super::parse_legacy_position("AA", "27"), Some((26, 26)));
+        assert_eq!(super::parse_legacy_position("D", "0"), None);
+    }
+
+    #[test]
+    pub fn large_boards() {
+        use crate::board::{Board, IntersectionState::*};
+        use crate::gamecommand::GameCommand;
+        use crate::gamestate::GameState;
+
+        let command = GameCommand::try_from_string(String::from("b am1"), 37, 37);
+        assert_eq!(command, Some(GameCommand::Set(36, 36, Black)));
+
+        let gamestate = GameState::with_board(Board::new(37))
+            .apply_command(command.unwrap())
+            .unwrap();
+        let error = gamestate
+            .apply_command(GameCommand::Set(36, 36, White))
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Intersection AM1 is already occupied");
+    }
+}
@@ -1,10 +1,42 @@
+use crate::coordinates::{off_board_position_name, position_name};
 use custom_error::custom_error;
 
 custom_error! {
     #[derive(Clone, PartialEq, Eq)]
     pub GoError
-    InvalidPosition{x: usize, y: usize, size: usize} = "Position (x: {x}, y: {y}) is invalid for {size}x{size} board",
+    InvalidPosition{x: usize, y: usize, width: usize, height: usize} = @{ format!("Position {} is off the {}x{} board", off_board_position_name(*x, *y, *height), width, height) },
     InvalidMove = "Couldn't parse move",
     NothingLeftToUndo = "Nothing left to undo",
+    NothingLeftToRedo = "Nothing left to redo",
+    NoSuchMove{move_number: usize} = "There is no move {move_number} in this line",
     KoViolation = "Violation of Ko",
+    GameOver = "The game is already over",
+    NotOver = "The game hasn't ended by passing",
+    NotYourTurn{to_move: &'static str} = "Not your turn, {to_move} is to move",
+    IntersectionOccupied{x: usize, y: usize, height: usize} = @{ format!("Intersection {} is already occupied", position_name(*x, *y, *height)) },
+    NoStone{x: usize, y: usize, height: usize} = @{ format!("There is no stone at {}", position_name(*x, *y, *height)) },
+    Suicide{x: usize, y: usize, height: usize} = @{ format!("Move at {} is suicide", position_name(*x, *y, *height)) },
+    UnknownRule{name: String} = "Unknown rule \"{name}\"",
+    SuperkoViolation{move_number: usize} = @{ match move_number {
+        0 => String::from("Violation of superko, position repeats the one before the first move"),
+        _ => format!("Violation of superko, position repeats the one after move {}", move_number),
+    } },
+    NoVariation{variation: usize} = "There is no variation {variation}",
+    NoSuchNode{node: usize} = "There is no node {node} in the game tree",
+    SgfUnexpectedCharacter{character: char, line: usize, column: usize} = "Unexpected character '{character}' in SGF at line {line}, column {column}",
+    SgfUnexpectedEnd{line: usize, column: usize} = "SGF ended unexpectedly at line {line}, column {column}",
+    SgfInvalidValue{property: String, value: String, line: usize, column: usize} = "Invalid value \"{value}\" for SGF property {property} at line {line}, column {column}",
+    SgfBoardTooLarge{width: usize, height: usize} = "SGF files can't hold a {width}x{height} board, only boards up to 52x52",
+    SgfIllegalMove{line: usize, column: usize, reason: String} = "Illegal move in SGF at line {line}, column {column}: {reason}",
+    InvalidVertex{vertex: String} = "Invalid vertex \"{vertex}\"",
+    EngineNotStarted{program: String, message: String} = "Couldn't start engine \"{program}\": {message}",
+    EngineCrashed{message: String} = "The engine stopped: {message}",
+    EngineTimeout{command: String, seconds: u64} = "The engine didn't answer \"{command}\" within {seconds} seconds",
+    EngineRejected{command: String, message: String} = "The engine rejected \"{command}\": {message}",
+    IllegalEngineMove{reply: String, reason: String} = "The engine replied with an illegal move \"{reply}\": {reason}",
+    UnknownCommand{name: String} = "Unknown command \"{name}\", type help to list the commands",
+    MissingArgument{usage: &'static str} = "Missing argument, usage: {usage}",
+    InvalidArgument{argument: String, usage: &'static str} = "Invalid argument \"{argument}\", usage: {usage}",
+    UnexpectedArgument{argument: String, usage: &'static str} = "Unexpected argument \"{argument}\", usage: {usage}",
+    FileError{path: String, message: String} = "Couldn't access file \"{path}\": {message}",
 }
@@ -1,59 +1,112 @@
 use crate::board::IntersectionState;
+use crate::coordinates;
 
 /// Defines a command to alter the gamestate
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum GameCommand {
     /// Sets an intersection at (0, 1) to a state (2)
     Set(usize, usize, IntersectionState),
-    /// Undo the previous move
+    /// Like Set, but ignores turn order and move legality;
+    /// Used to set up positions
+    Setup(usize, usize, IntersectionState),
+    /// Undo the previous move, keeping it to be redone
     Undo,
+    /// Redo the next move of the current line
+    Redo,
+    /// Go to the position after the given number of moves of the current line
+    GoTo(usize),
+    /// Go to the start of the game
+    First,
+    /// Go to the end of the current line
+    Last,
+    /// The player to move passes their turn
+    Pass,
+    /// The given player resigns
+    Resign(IntersectionState),
+    /// Resumes a game which ended by passing, for example if the players disagree on dead stones
+    Resume,
 }
 
 impl GameCommand {
-    /// Tries to parse a string into a gamecommand
-    pub fn try_from_string(input: String) -> Option<GameCommand> {
+    /// Tries to parse a string into a gamecommand, for a board of the given size
+    pub fn try_from_string(input: String, width: usize, height: usize) -> Option<GameCommand> {
         let as_lowercase = input.to_lowercase();
 
         if as_lowercase == "undo" || as_lowercase == "u" {
             return Some(Self::Undo);
         }
 
-        // Set
-        // Should be in a format of w;A;5 or b;b;6
-        if as_lowercase.starts_with("w") || as_lowercase.starts_with("b") {
-            let params = as_lowercase
-                .replace(" ", "")
-                .split(";")
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>();
+        if as_lowercase == "redo" || as_lowercase == "r" {
+            return Some(Self::Redo);
+        }
 
-            let state = params.get(0)?;
-            let state = match state.as_str() {
+        if as_lowercase == "first" {
+            return Some(Self::First);
+        }
+
+        if as_lowercase == "last" {
+            return Some(Self::Last);
+        }
+
+        // Should be in a format of goto 12
+        if let Some(move_number) = as_lowercase.strip_prefix("goto ") {
+            return Some(Self::GoTo(move_number.trim().parse::<usize>().ok()?));
+        }
+
+        if as_lowercase == "pass" || as_lowercase == "p" {
+            return Some(Self::Pass);
+        }
+
+        if as_lowercase == "resume" {
+            return Some(Self::Resume);
+        }
+
+        // Resign
+        // Should be in a format of resign;w or resign b
+        if let Some(player) = as_lowercase.strip_prefix("resign") {
+            let state = match player.trim_start_matches([';', ' ']) {
                 "w" | "white" => IntersectionState::White,
                 "b" | "black" => IntersectionState::Black,
                 &_ => return None,
             };
 
-            let x_as_ascii = params.get(1)?.to_uppercase().chars().nth(0)?;
-            let mut x_result: Option<usize> = None;
+            return Some(Self::Resign(state));
+        }
 
-            let mut ascii_chars = crate::ASCII.chars();
-            for i in 0..crate::ASCII.len() {
-                if ascii_chars.next()? == x_as_ascii {
-                    x_result = Some(i);
-                    break;
-                }
-            }
+        // Set
+        // Should be in a format of b D4 or w q16, or the legacy format of w;A;5 or b;b;6
+        if as_lowercase.starts_with("w") || as_lowercase.starts_with("b") {
+            let legacy = as_lowercase.contains(';');
+            let params = if legacy {
+                as_lowercase
+                    .replace(" ", "")
+                    .split(";")
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+            } else {
+                as_lowercase
+                    .split_whitespace()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+            };
 
-            let y_result = params.get(2)?.parse::<usize>().ok();
+            let state = params.first()?;
+            let state = match state.as_str() {
+                "w" | "white" => IntersectionState::White,
+                "b" | "black" => IntersectionState::Black,
+                &_ => return None,
+            };
 
-            let x = x_result?;
-            // Y is rendered with a + 1 so we don't start from 0
-            let y = y_result? - 1;
+            let (x, y) = match (legacy, params.len()) {
+                (true, 3) => coordinates::parse_legacy_position(&params[1], &params[2])
+                    .filter(|&(x, y)| x < width && y < height)?,
+                (false, 2) => coordinates::parse_position(&params[1], width, height)?,
+                _ => return None,
+            };
 
             return Some(Self::Set(x, y, state));
         }
 
-        return None;
+        None
     }
 }
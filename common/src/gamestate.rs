@@ -1,5 +1,12 @@
 use crate::board::{Board, IntersectionState};
+use crate::errors::GoError;
 use crate::gamecommand::GameCommand;
+use crate::gametree::{GameNode, GameTree};
+use crate::ruleset::{KoRule, Ruleset, SuicideRule};
+use crate::scoring::ScoreReport;
+use crate::zobrist;
+
+use std::collections::HashSet;
 
 /// Defines a state in play, with all the necessary data to end the game.
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
@@ -9,6 +16,8 @@ pub struct BoardState {
     pub white_prisoners: usize,
     /// The number of stones black has captured
     pub black_prisoners: usize,
+    /// The command which lead to this state, if any
+    pub last_move: Option<GameCommand>,
 }
 
 impl BoardState {
@@ -17,138 +26,1047 @@ impl BoardState {
             board: Board::default(),
             white_prisoners: 0,
             black_prisoners: 0,
+            last_move: None,
         }
     }
 
+    /// Returns the Zobrist hash of the position, optionally along with the player to move
+    pub fn position_hash(&self, to_move: Option<IntersectionState>) -> u64 {
+        self.board.zobrist_hash() ^ to_move.map_or(0, zobrist::side_to_move_key)
+    }
+
     /// Removes dead groups from the board and updates the counts, returning an updated boardstate
     ///
-    /// Optionally you can set last_move, which will process that move last, to have a proper result with ko
+    /// Optionally you can set last_move, which will process that move's group last, to have a proper result with ko
     pub fn removed_dead_groups(&self, last_move: Option<(usize, usize)>) -> Self {
-        let mut cloned = Self::clone(&self);
+        let mut cloned = Self::clone(self);
 
-        for y in 0..cloned.board.size() {
-            for x in 0..cloned.board.size() {
+        for y in 0..cloned.board.height() {
+            for x in 0..cloned.board.width() {
                 if let Some(priority) = last_move {
-                    if (x, y) == priority {
+                    if cloned.board.same_group((x, y), priority) {
                         // Check this one last
                         continue;
                     }
                 }
 
-                let state = cloned.board.get_intersection(x, y).unwrap();
-                if state != IntersectionState::Empty {
-                    let group = cloned.board.find_intersections_in_group(x, y).unwrap();
-                    let liberties = cloned.board.find_true_liberties(x, y).unwrap();
-
-                    if liberties.len() == 0 {
-                        for intersection in group.iter() {
-                            cloned
-                                .board
-                                .set_intersection(
-                                    intersection.0,
-                                    intersection.1,
-                                    IntersectionState::Empty,
-                                )
-                                .unwrap();
-
-                            match state {
-                                IntersectionState::Empty => unreachable!(),
-                                IntersectionState::Black => {
-                                    cloned.white_prisoners += 1;
-                                }
-                                IntersectionState::White => {
-                                    cloned.black_prisoners += 1;
-                                }
-                            }
-                        }
-                    }
-                }
+                cloned.remove_group_without_liberties(x, y);
             }
         }
 
         if let Some(priority) = last_move {
-            let state = cloned
-                .board
-                .get_intersection(priority.0, priority.1)
-                .unwrap();
-            if state != IntersectionState::Empty {
-                let group = cloned
-                    .board
-                    .find_intersections_in_group(priority.0, priority.1)
-                    .unwrap();
-                let liberties = cloned
-                    .board
-                    .find_true_liberties(priority.0, priority.1)
-                    .unwrap();
-
-                if liberties.len() == 0 {
-                    for intersection in group.iter() {
-                        cloned
-                            .board
-                            .set_intersection(
-                                intersection.0,
-                                intersection.1,
-                                IntersectionState::Empty,
-                            )
-                            .unwrap();
-
-                        match state {
-                            IntersectionState::Empty => unreachable!(),
-                            IntersectionState::Black => {
-                                cloned.white_prisoners += 1;
-                            }
-                            IntersectionState::White => {
-                                cloned.black_prisoners += 1;
-                            }
-                        }
-                    }
-                }
+            cloned.remove_group_without_liberties(priority.0, priority.1);
+        }
+
+        cloned
+    }
+
+    /// Removes the group at x, y if it has no liberties, counting its stones as prisoners
+    fn remove_group_without_liberties(&mut self, x: usize, y: usize) {
+        let Some(state) = self.board.get_intersection(x, y) else {
+            return;
+        };
+
+        if state == IntersectionState::Empty || self.board.group_has_liberties(x, y) != Ok(false) {
+            return;
+        }
+
+        let removed = self.board.remove_group(x, y).unwrap_or_default();
+
+        match state {
+            IntersectionState::Empty => unreachable!(),
+            IntersectionState::Black => {
+                self.white_prisoners += removed;
+            }
+            IntersectionState::White => {
+                self.black_prisoners += removed;
             }
         }
+    }
+}
+
+/// Describes whether a game is still going, and how it ended
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum GameStatus {
+    #[default]
+    Playing,
+    /// Both players passed, one after the other;
+    /// Dead stones still need to be agreed on
+    Passed,
+    /// The given player resigned
+    Resigned(IntersectionState),
+    /// The game ended by passing, and was scored
+    Scored(ScoreReport),
+}
 
-        return cloned;
+impl GameStatus {
+    /// Returns whether the game has ended
+    pub fn is_over(&self) -> bool {
+        *self != Self::Playing
     }
 }
 
+/// Information about a game, which isn't needed to play it
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GameInfo {
+    pub black_player: String,
+    pub white_player: String,
+}
+
 /// Defines a general state of the game.
 ///
 /// This is the most high level game related struct.
 ///
-/// Holds a history of boardstates, so we can use undo
-#[derive(Clone, PartialEq, Eq, Debug, Default)]
+/// Holds a tree of boardstates, so we can undo moves and explore variations
+#[derive(Clone, PartialEq, Debug)]
 pub struct GameState {
-    pub history: Vec<BoardState>,
+    /// Every position reached so far, along with the one we are at
+    tree: GameTree,
+    /// The rules this game is played under
+    pub ruleset: Ruleset,
+    /// The player whose turn it is
+    pub to_move: IntersectionState,
+    /// Whether the game is still going
+    pub status: GameStatus,
+    /// Player names and such, as written into game records
+    pub info: GameInfo,
+    /// Hashes of the positions before the current one, from the start of the game,
+    /// with the player to move mixed in; Used to look for repeated positions.
+    positions: HashSet<u64>,
 }
 
 impl GameState {
     pub fn new() -> Self {
+        Self::with_board(Board::default())
+    }
+
+    /// Creates a game which starts on the given board
+    pub fn with_board(board: Board) -> Self {
+        let boardstate = BoardState {
+            board,
+            ..BoardState::new()
+        };
+
         GameState {
-            history: vec![BoardState::new()],
+            tree: GameTree::new(GameNode::new(
+                boardstate,
+                IntersectionState::Black,
+                GameStatus::Playing,
+            )),
+            ruleset: Ruleset::default(),
+            to_move: IntersectionState::Black,
+            status: GameStatus::Playing,
+            info: GameInfo::default(),
+            positions: HashSet::new(),
+        }
+    }
+
+    /// Returns the tree of every position reached so far
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    /// Returns the boardstate we are at
+    pub fn current(&self) -> &BoardState {
+        &self.tree.current_node().boardstate
+    }
+
+    /// Returns the boardstates from the start of the game up to the current one
+    pub fn history(&self) -> Vec<&BoardState> {
+        self.tree
+            .path()
+            .into_iter()
+            .map(|index| &self.tree.node(index).unwrap().boardstate)
+            .collect()
+    }
+
+    /// Returns the player to move at the given node of the game tree
+    pub fn to_move_at(&self, node: usize) -> IntersectionState {
+        if node == self.tree.current() {
+            return self.to_move;
+        }
+
+        self.tree
+            .node(node)
+            .map(|node| node.to_move)
+            .unwrap_or(self.to_move)
+    }
+
+    /// Returns the game status at the given node of the game tree
+    pub fn status_at(&self, node: usize) -> GameStatus {
+        if node == self.tree.current() {
+            return self.status;
+        }
+
+        self.tree
+            .node(node)
+            .map(|node| node.status)
+            .unwrap_or(self.status)
+    }
+
+    /// Sets the comment of the current position
+    pub fn set_comment(&mut self, comment: String) {
+        self.tree.current_node_mut().comment = comment;
+    }
+
+    /// Stores whose turn it is and the game status in the current node, to restore them when we come back
+    fn save_current(&mut self) {
+        let node = self.tree.current_node_mut();
+        node.to_move = self.to_move;
+        node.status = self.status;
+    }
+
+    /// Moves on to a new boardstate after the current one.
+    ///
+    /// If the same move was already played here, we follow it instead of adding a variation.
+    fn push(&mut self, boardstate: BoardState) {
+        self.save_current();
+        self.positions
+            .insert(self.current().position_hash(Some(self.to_move)));
+
+        let existing = self
+            .tree
+            .current_node()
+            .children()
+            .iter()
+            .copied()
+            .find(|&child| self.tree.node(child).unwrap().boardstate == boardstate);
+
+        match existing {
+            Some(child) => {
+                self.tree.set_current(child);
+            }
+            None => {
+                self.tree
+                    .add_child(GameNode::new(boardstate, self.to_move, self.status));
+            }
+        }
+    }
+
+    /// Moves to another node of the game tree, restoring whose turn it was and the game status there
+    pub fn go_to(&mut self, node: usize) -> Result<(), GoError> {
+        if self.tree.node(node).is_none() {
+            return Err(GoError::NoSuchNode { node });
+        }
+
+        self.save_current();
+        self.tree.set_current(node);
+
+        let mut path = self.tree.path();
+        path.pop();
+
+        self.positions = path
+            .into_iter()
+            .map(|index| {
+                let node = self.tree.node(index).unwrap();
+                node.boardstate.position_hash(Some(node.to_move))
+            })
+            .collect();
+
+        let node = self.tree.current_node();
+        self.to_move = node.to_move;
+        self.status = node.status;
+        Ok(())
+    }
+
+    /// Goes back to the position before the current one
+    pub fn to_parent(&mut self) -> Result<(), GoError> {
+        let parent = self
+            .tree
+            .current_node()
+            .parent()
+            .ok_or(GoError::NothingLeftToUndo)?;
+
+        self.go_to(parent)
+    }
+
+    /// Goes forward to the position after the current one, in the given variation.
+    ///
+    /// Variation 0 is the main line.
+    pub fn to_child(&mut self, variation: usize) -> Result<(), GoError> {
+        let child =
+            *self
+                .tree
+                .current_node()
+                .children()
+                .get(variation)
+                .ok_or(GoError::NoVariation {
+                    variation: variation + 1,
+                })?;
+
+        self.go_to(child)
+    }
+
+    /// Switches to another variation of the current move
+    pub fn to_sibling(&mut self, variation: usize) -> Result<(), GoError> {
+        let sibling = self
+            .tree
+            .current_node()
+            .parent()
+            .and_then(|parent| self.tree.node(parent).unwrap().children().get(variation))
+            .copied()
+            .ok_or(GoError::NoVariation {
+                variation: variation + 1,
+            })?;
+
+        self.go_to(sibling)
+    }
+
+    /// Makes the line leading to the current position the main line
+    pub fn promote_variation(&mut self) {
+        self.tree.promote(self.tree.current());
+    }
+
+    /// Makes the current position replace the main line after the previous position,
+    /// removing the moves which followed it
+    pub fn overwrite_line(&mut self) {
+        let current = self.tree.current();
+
+        let Some(parent) = self.tree.current_node().parent() else {
+            return;
+        };
+
+        let main_line = self.tree.node(parent).unwrap().children()[0];
+
+        if main_line != current {
+            self.tree.remove(main_line);
+        }
+        self.promote_variation();
+    }
+
+    /// Returns whether a node was reached by a move, rather than setup or resigning
+    fn is_move(&self, node: usize) -> bool {
+        matches!(
+            self.tree.node(node).unwrap().boardstate.last_move,
+            Some(GameCommand::Set(..)) | Some(GameCommand::Pass)
+        )
+    }
+
+    /// Returns the number of moves played to reach the current position
+    pub fn move_number(&self) -> usize {
+        self.tree
+            .path()
+            .into_iter()
+            .filter(|&node| self.is_move(node))
+            .count()
+    }
+
+    /// Returns the number of moves in the current line, including the ones after the current position
+    pub fn total_moves(&self) -> usize {
+        self.tree
+            .line()
+            .into_iter()
+            .filter(|&node| self.is_move(node))
+            .count()
+    }
+
+    /// Returns the last node of the current line which is reached after the given number of moves
+    fn node_after_moves(&self, move_number: usize) -> Result<usize, GoError> {
+        let mut moves = 0;
+        let mut found = None;
+
+        for node in self.tree.line() {
+            if self.is_move(node) {
+                moves += 1;
+            }
+
+            match moves.cmp(&move_number) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => found = Some(node),
+                std::cmp::Ordering::Greater => break,
+            }
+        }
+
+        found.ok_or(GoError::NoSuchMove { move_number })
+    }
+
+    /// Checks whether a new boardstate, reached by a move of the given player, repeats an earlier
+    /// position in a way that isn't allowed by the ko rule
+    fn check_repetition(
+        &self,
+        boardstate: &BoardState,
+        player: IntersectionState,
+    ) -> Result<(), GoError> {
+        let hash = boardstate.board.zobrist_hash();
+
+        // Whether a position, with the player to move, came up before
+        let current = self.current().position_hash(Some(self.to_move));
+        let seen = |situation: u64| situation == current || self.positions.contains(&situation);
+
+        let repeated = match self.ruleset.ko_rule {
+            KoRule::None => false,
+            KoRule::Simple => {
+                // A move may not recreate the position from before the previous move
+                let path = self.tree.path();
+                path.len() >= 2
+                    && self
+                        .tree
+                        .node(path[path.len() - 2])
+                        .is_some_and(|node| node.boardstate.board.zobrist_hash() == hash)
+            }
+            // Situational superko only cares if the same player would be to move
+            KoRule::SituationalSuperko => seen(boardstate.position_hash(Some(player.opposite()))),
+            KoRule::PositionalSuperko => {
+                seen(boardstate.position_hash(Some(IntersectionState::Black)))
+                    || seen(boardstate.position_hash(Some(IntersectionState::White)))
+            }
+        };
+
+        if !repeated {
+            return Ok(());
         }
+
+        if self.ruleset.ko_rule == KoRule::Simple {
+            return Err(GoError::KoViolation);
+        }
+
+        // Find where the position came up, which is rare enough to not need a lookup;
+        // Moves are counted like move_number does, so setup nodes don't count
+        let path = self.tree.path();
+        let Some(index) = path.iter().position(|&node| {
+            let board = &self.tree.node(node).unwrap().boardstate.board;
+
+            board.zobrist_hash() == hash
+                && (self.ruleset.ko_rule != KoRule::SituationalSuperko
+                    || self.to_move_at(node) == player.opposite())
+        }) else {
+            // The hashes of two different positions can only collide by chance
+            return Ok(());
+        };
+
+        let move_number = path[..=index]
+            .iter()
+            .filter(|&&node| self.is_move(node))
+            .count();
+
+        Err(GoError::SuperkoViolation { move_number })
+    }
+
+    /// Applies a move on behalf of the given player, even if it isn't their turn
+    /// or the game ended by passing.
+    ///
+    /// Used where the player of every move is given, like in game records or GTP.
+    pub fn apply_command_as(
+        &self,
+        player: IntersectionState,
+        command: GameCommand,
+    ) -> Result<Self, GoError> {
+        let mut gamestate = self.clone();
+
+        if gamestate.status == GameStatus::Passed {
+            gamestate = gamestate.apply_command(GameCommand::Resume)?;
+        }
+        gamestate.to_move = player;
+
+        gamestate.apply_command(command)
     }
 
     /// Applies a command to the gamestate and returns a copy of self after the command
-    pub fn apply_command(&self, command: GameCommand) -> Option<Self> {
+    pub fn apply_command(&self, command: GameCommand) -> Result<Self, GoError> {
         let mut cloned = self.clone();
 
+        if cloned.status.is_over()
+            && matches!(
+                command,
+                GameCommand::Set(..) | GameCommand::Pass | GameCommand::Resign(_)
+            )
+        {
+            return Err(GoError::GameOver);
+        }
+
         match command {
             GameCommand::Set(x, y, state) => {
-                let mut boardstate = cloned.history.get(cloned.history.len() - 1)?.clone();
-                boardstate.board.set_intersection(x, y, state).ok()?;
+                if state == IntersectionState::Empty {
+                    return Err(GoError::InvalidMove);
+                }
+
+                if state != cloned.to_move {
+                    return Err(GoError::NotYourTurn {
+                        to_move: cloned.to_move.name(),
+                    });
+                }
+
+                let mut placed = cloned.current().clone();
+
+                match placed.board.get_intersection(x, y) {
+                    Some(IntersectionState::Empty) => {}
+                    Some(_) => {
+                        return Err(GoError::IntersectionOccupied {
+                            x,
+                            y,
+                            height: placed.board.height(),
+                        })
+                    }
+                    None => {
+                        return Err(GoError::InvalidPosition {
+                            x,
+                            y,
+                            width: placed.board.width(),
+                            height: placed.board.height(),
+                        })
+                    }
+                }
+
+                placed.board.set_intersection(x, y, state)?;
+
+                let mut boardstate = placed.removed_dead_groups(Some((x, y)));
+                boardstate.last_move = Some(command);
+
+                // The placed stone was removed with its own group, so the move was suicide
+                if boardstate.board.get_intersection(x, y) == Some(IntersectionState::Empty) {
+                    let group = placed.board.find_intersections_in_group(x, y)?;
+
+                    // Single stone suicide would just recreate the previous position
+                    if cloned.ruleset.suicide_rule == SuicideRule::Forbidden || group.len() == 1 {
+                        return Err(GoError::Suicide {
+                            x,
+                            y,
+                            height: placed.board.height(),
+                        });
+                    }
+                }
+
+                cloned.check_repetition(&boardstate, state)?;
+
+                cloned.push(boardstate);
+                cloned.to_move = state.opposite();
+            }
+            GameCommand::Setup(x, y, state) => {
+                let before = cloned.current();
+                let mut boardstate = before.clone();
+                boardstate.board.set_intersection(x, y, state)?;
+
+                // Groups left without liberties are removed, but nobody captured them
                 boardstate = boardstate.removed_dead_groups(Some((x, y)));
-                cloned.history.push(boardstate);
+                boardstate.black_prisoners = before.black_prisoners;
+                boardstate.white_prisoners = before.white_prisoners;
+                boardstate.last_move = Some(command);
+
+                cloned.push(boardstate);
             }
-            GameCommand::Undo => {
-                // We can't undo, there is nothing left
-                if cloned.history.len() < 2 {
-                    return None;
+            GameCommand::Pass => {
+                let mut boardstate = cloned.current().clone();
+
+                // Two passes in a row end the game
+                let ends_game = boardstate.last_move == Some(GameCommand::Pass);
+
+                boardstate.last_move = Some(command);
+                cloned.push(boardstate);
+                cloned.to_move = cloned.to_move.opposite();
+
+                if ends_game {
+                    cloned.status = GameStatus::Passed;
+                }
+            }
+            GameCommand::Resign(state) => {
+                if state == IntersectionState::Empty {
+                    return Err(GoError::InvalidMove);
                 }
 
-                // Remove the latest boardstate from history, which will make the one before it the latest
-                cloned.history.remove(cloned.history.len() - 1);
+                let mut boardstate = cloned.current().clone();
+                boardstate.last_move = Some(command);
+                cloned.push(boardstate);
+                cloned.status = GameStatus::Resigned(state);
             }
+            GameCommand::Resume => {
+                if cloned.status != GameStatus::Passed {
+                    return Err(GoError::NotOver);
+                }
+
+                let mut boardstate = cloned.current().clone();
+                boardstate.last_move = Some(command);
+                cloned.push(boardstate);
+                cloned.status = GameStatus::Playing;
+            }
+            GameCommand::Undo => {
+                // Go back to the previous position, keeping the undone move as a variation
+                cloned.to_parent()?;
+            }
+            GameCommand::Redo => {
+                cloned.to_child(0).map_err(|_| GoError::NothingLeftToRedo)?;
+            }
+            GameCommand::GoTo(move_number) => {
+                let node = cloned.node_after_moves(move_number)?;
+                cloned.go_to(node)?;
+            }
+            GameCommand::First => {
+                let node = cloned.node_after_moves(0)?;
+                cloned.go_to(node)?;
+            }
+            GameCommand::Last => {
+                let node = cloned.tree.main_line_end(cloned.tree.current());
+                cloned.go_to(node)?;
+            }
+        }
+
+        cloned.save_current();
+        Ok(cloned)
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameState, GameStatus};
+    use crate::board::IntersectionState::{self, Black, White};
+    use crate::errors::GoError;
+    use crate::gamecommand::GameCommand;
+    use crate::ruleset::{KoRule, SuicideRule};
+
+    /// Places a sequence of stones in setup mode, panicking if any of them is rejected
+    fn setup(gamestate: GameState, stones: &[(usize, usize, IntersectionState)]) -> GameState {
+        let mut gamestate = gamestate;
+        for (x, y, state) in stones {
+            gamestate = gamestate
+                .apply_command(GameCommand::Setup(*x, *y, *state))
+                .unwrap();
+        }
+        gamestate
+    }
+
+    /// Plays a sequence of stones, panicking if any of them is rejected
+    fn play(gamestate: GameState, moves: &[(usize, usize, IntersectionState)]) -> GameState {
+        let mut gamestate = gamestate;
+        for (x, y, state) in moves {
+            gamestate = gamestate
+                .apply_command(GameCommand::Set(*x, *y, *state))
+                .unwrap();
         }
+        gamestate
+    }
+
+    #[test]
+    pub fn turn_order() {
+        let gamestate = play(GameState::new(), &[(4, 4, Black)]);
+        assert_eq!(gamestate.to_move, White);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(3, 3, Black)),
+            Err(GoError::NotYourTurn { to_move: "White" })
+        );
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(4, 4, White)),
+            Err(GoError::IntersectionOccupied {
+                x: 4,
+                y: 4,
+                height: 9
+            })
+        );
+
+        // Undo gives the turn back
+        let gamestate = gamestate.apply_command(GameCommand::Undo).unwrap();
+        assert_eq!(gamestate.to_move, Black);
+    }
+
+    #[test]
+    pub fn setup_ignores_turn() {
+        let gamestate = setup(
+            GameState::new(),
+            &[(0, 0, White), (1, 0, White), (2, 2, Black)],
+        );
+        assert_eq!(gamestate.to_move, Black);
+
+        // Setup can also clear intersections
+        let gamestate = setup(gamestate, &[(1, 0, IntersectionState::Empty)]);
+        assert_eq!(
+            gamestate.current().board.get_intersection(1, 0),
+            Some(IntersectionState::Empty)
+        );
+
+        let gamestate = gamestate.apply_command(GameCommand::Undo).unwrap();
+        assert_eq!(gamestate.to_move, Black);
+
+        // Surrounding the white stones in the corner removes them without counting prisoners
+        let gamestate = setup(gamestate, &[(0, 1, Black), (1, 1, Black), (2, 0, Black)]);
+        let boardstate = gamestate.current();
+        assert_eq!(
+            boardstate.board.get_intersection(0, 0),
+            Some(IntersectionState::Empty)
+        );
+        assert_eq!(boardstate.black_prisoners, 0);
+        assert_eq!(boardstate.white_prisoners, 0);
+    }
+
+    #[test]
+    pub fn corner_ko() {
+        let gamestate = setup(
+            GameState::new(),
+            &[(0, 0, White), (0, 1, Black), (2, 0, White), (1, 1, White)],
+        );
+
+        // Black takes the ko
+        let gamestate = play(gamestate, &[(1, 0, Black)]);
+        assert_eq!(
+            gamestate.current().board.get_intersection(0, 0),
+            Some(IntersectionState::Empty)
+        );
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(0, 0, White)),
+            Err(GoError::KoViolation)
+        );
+    }
+
+    #[test]
+    pub fn edge_ko() {
+        let gamestate = setup(
+            GameState::new(),
+            &[
+                (4, 0, White),
+                (3, 0, Black),
+                (5, 0, Black),
+                (3, 1, White),
+                (5, 1, White),
+                (4, 2, White),
+            ],
+        );
+
+        let gamestate = play(gamestate, &[(4, 1, Black)]);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(4, 0, White)),
+            Err(GoError::KoViolation)
+        );
+    }
+
+    #[test]
+    pub fn center_ko() {
+        let gamestate = setup(
+            GameState::new(),
+            &[
+                (4, 4, White),
+                (3, 4, Black),
+                (4, 3, Black),
+                (4, 5, Black),
+                (6, 4, White),
+                (5, 3, White),
+                (5, 5, White),
+            ],
+        );
+
+        let gamestate = play(gamestate, &[(5, 4, Black)]);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(4, 4, White)),
+            Err(GoError::KoViolation)
+        );
+
+        // After a move elsewhere, the ko may be retaken
+        let gamestate = play(gamestate, &[(0, 8, White), (8, 0, Black), (4, 4, White)]);
+        assert_eq!(
+            gamestate.current().board.get_intersection(5, 4),
+            Some(IntersectionState::Empty)
+        );
+        assert_eq!(gamestate.current().black_prisoners, 1);
+        assert_eq!(gamestate.current().white_prisoners, 1);
+    }
+
+    /// Sets up a center ko where black can capture, and a corner ko where white can capture
+    fn double_ko(ko_rule: KoRule) -> GameState {
+        let mut gamestate = GameState::new();
+        gamestate.ruleset.ko_rule = ko_rule;
+
+        setup(
+            gamestate,
+            &[
+                (4, 4, White),
+                (6, 4, White),
+                (5, 3, White),
+                (5, 5, White),
+                (3, 4, Black),
+                (4, 3, Black),
+                (4, 5, Black),
+                (0, 0, Black),
+                (0, 1, White),
+                (2, 0, Black),
+                (1, 1, Black),
+            ],
+        )
+    }
+
+    /// Takes both kos, then retakes both, with black passing inbetween.
+    ///
+    /// Returns the state before the final retake at (0, 0), which recreates the starting position
+    /// with white to move, whereas black was to move at the start.
+    fn double_ko_cycle(ko_rule: KoRule) -> GameState {
+        let gamestate = play(double_ko(ko_rule), &[(5, 4, Black), (1, 0, White)]);
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+        play(gamestate, &[(4, 4, White)])
+    }
+
+    #[test]
+    pub fn positional_superko() {
+        let gamestate = double_ko_cycle(KoRule::PositionalSuperko);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(0, 0, Black)),
+            Err(GoError::SuperkoViolation { move_number: 0 })
+        );
+        // The setup stones aren't moves, so the repeated position is the one before the first move
+        assert_eq!(
+            GoError::SuperkoViolation { move_number: 0 }.to_string(),
+            "Violation of superko, position repeats the one before the first move"
+        );
+
+        // Simple ko only looks at the position before the previous move
+        assert!(double_ko_cycle(KoRule::Simple)
+            .apply_command(GameCommand::Set(0, 0, Black))
+            .is_ok());
+    }
+
+    #[test]
+    pub fn situational_superko() {
+        // The position repeats, but now with white to move instead of black
+        assert!(double_ko_cycle(KoRule::SituationalSuperko)
+            .apply_command(GameCommand::Set(0, 0, Black))
+            .is_ok());
+
+        // Repeating the position with the same player to move is not allowed
+        let gamestate = play(
+            double_ko(KoRule::SituationalSuperko),
+            &[(5, 4, Black), (1, 0, White)],
+        );
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(0, 0, Black)),
+            Err(GoError::SuperkoViolation { move_number: 1 })
+        );
+    }
+
+    #[test]
+    pub fn no_ko_rule() {
+        let gamestate = play(double_ko(KoRule::None), &[(5, 4, Black)]);
+
+        assert!(gamestate
+            .apply_command(GameCommand::Set(4, 4, White))
+            .is_ok());
+    }
+
+    #[test]
+    pub fn single_stone_suicide() {
+        let gamestate = setup(GameState::new(), &[(1, 0, White), (0, 1, White)]);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(0, 0, Black)),
+            Err(GoError::Suicide {
+                x: 0,
+                y: 0,
+                height: 9
+            })
+        );
+
+        // Not even allowed when suicide is
+        let mut gamestate = gamestate;
+        gamestate.ruleset.suicide_rule = SuicideRule::Allowed;
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(0, 0, Black)),
+            Err(GoError::Suicide {
+                x: 0,
+                y: 0,
+                height: 9
+            })
+        );
+    }
+
+    #[test]
+    pub fn multi_stone_suicide() {
+        let gamestate = setup(
+            GameState::new(),
+            &[(0, 0, Black), (2, 0, White), (0, 1, White), (1, 1, White)],
+        );
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(1, 0, Black)),
+            Err(GoError::Suicide {
+                x: 1,
+                y: 0,
+                height: 9
+            })
+        );
+
+        let mut gamestate = gamestate;
+        gamestate.ruleset.suicide_rule = SuicideRule::Allowed;
+
+        let gamestate = play(gamestate, &[(1, 0, Black)]);
+        let latest = gamestate.current();
+
+        assert_eq!(
+            latest.board.get_intersection(0, 0),
+            Some(IntersectionState::Empty)
+        );
+        assert_eq!(
+            latest.board.get_intersection(1, 0),
+            Some(IntersectionState::Empty)
+        );
+        assert_eq!(latest.white_prisoners, 2);
+    }
+
+    #[test]
+    pub fn capture_is_not_suicide() {
+        // Black has no liberties at (0, 0) after playing, but captures the white stone at (1, 0)
+        let gamestate = setup(
+            GameState::new(),
+            &[(1, 0, White), (0, 1, White), (2, 0, Black), (1, 1, Black)],
+        );
+
+        let gamestate = play(gamestate, &[(0, 0, Black)]);
+        let latest = gamestate.current();
+
+        assert_eq!(
+            latest.board.get_intersection(1, 0),
+            Some(IntersectionState::Empty)
+        );
+        assert_eq!(latest.black_prisoners, 1);
+    }
+
+    #[test]
+    pub fn two_passes_end_the_game() {
+        let gamestate = play(GameState::new(), &[(4, 4, Black)]);
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+        assert_eq!(gamestate.to_move, Black);
+        assert_eq!(gamestate.status, GameStatus::Playing);
+
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Passed);
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Set(3, 3, White)),
+            Err(GoError::GameOver)
+        );
+
+        // Undoing the last pass resumes the game
+        let gamestate = gamestate.apply_command(GameCommand::Undo).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Playing);
+        assert_eq!(gamestate.to_move, Black);
+    }
+
+    #[test]
+    pub fn undo_keeps_variations() {
+        let gamestate = play(GameState::new(), &[(4, 4, Black), (3, 3, White)]);
+        let main_line = gamestate.tree().current();
+
+        // Playing something else after an undo starts a variation
+        let gamestate = gamestate.apply_command(GameCommand::Undo).unwrap();
+        let mut gamestate = play(gamestate, &[(5, 5, White)]);
+        let variation = gamestate.tree().current();
+
+        assert_eq!(gamestate.history().len(), 3);
+        assert_eq!(gamestate.to_move, Black);
+
+        gamestate.to_parent().unwrap();
+        assert_eq!(
+            gamestate.tree().current_node().children(),
+            &[main_line, variation]
+        );
+        assert_eq!(gamestate.to_move, White);
+
+        // Replaying a move follows the existing variation
+        let mut gamestate = play(gamestate, &[(3, 3, White)]);
+        assert_eq!(gamestate.tree().current(), main_line);
+
+        gamestate.to_sibling(1).unwrap();
+        assert_eq!(gamestate.tree().current(), variation);
+        assert_eq!(
+            gamestate.to_sibling(2),
+            Err(GoError::NoVariation { variation: 3 })
+        );
+
+        gamestate.promote_variation();
+        gamestate.to_parent().unwrap();
+        gamestate.to_child(0).unwrap();
+        assert_eq!(gamestate.tree().current(), variation);
+        assert_eq!(
+            gamestate.current().board.get_intersection(5, 5),
+            Some(White)
+        );
+    }
+
+    #[test]
+    pub fn navigation() {
+        let gamestate = setup(GameState::new(), &[(0, 0, Black)]);
+        let gamestate = play(gamestate, &[(4, 4, Black), (3, 3, White), (5, 5, Black)]);
+        assert_eq!(gamestate.move_number(), 3);
+
+        // The start is after the setup stones
+        let gamestate = gamestate.apply_command(GameCommand::First).unwrap();
+        assert_eq!(gamestate.move_number(), 0);
+        assert_eq!(gamestate.total_moves(), 3);
+        assert_eq!(gamestate.history().len(), 2);
+
+        let gamestate = gamestate.apply_command(GameCommand::GoTo(2)).unwrap();
+        assert_eq!(gamestate.move_number(), 2);
+        assert_eq!(gamestate.to_move, Black);
+
+        let gamestate = gamestate.apply_command(GameCommand::Redo).unwrap();
+        assert_eq!(gamestate.move_number(), 3);
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Redo),
+            Err(GoError::NothingLeftToRedo)
+        );
+        assert_eq!(
+            gamestate.apply_command(GameCommand::GoTo(4)),
+            Err(GoError::NoSuchMove { move_number: 4 })
+        );
+
+        // Overwriting after an undo throws the rest of the line away
+        let gamestate = gamestate.apply_command(GameCommand::GoTo(1)).unwrap();
+        let gamestate = play(gamestate, &[(2, 2, White)]);
+        let gamestate = gamestate.apply_command(GameCommand::First).unwrap();
+        let gamestate = gamestate.apply_command(GameCommand::Last).unwrap();
+        assert_eq!(gamestate.move_number(), 3);
+
+        let mut gamestate = gamestate.apply_command(GameCommand::GoTo(2)).unwrap();
+        gamestate.to_sibling(1).unwrap();
+        gamestate.overwrite_line();
+
+        assert_eq!(gamestate.total_moves(), 2);
+        assert_eq!(gamestate.tree().len(), 4);
+        assert_eq!(
+            gamestate.current().board.get_intersection(2, 2),
+            Some(White)
+        );
+    }
+
+    #[test]
+    pub fn resign() {
+        let gamestate = play(GameState::new(), &[(4, 4, Black)]);
+        let gamestate = gamestate.apply_command(GameCommand::Resign(White)).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Resigned(White));
+
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Pass),
+            Err(GoError::GameOver)
+        );
+
+        let gamestate = gamestate.apply_command(GameCommand::Undo).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Playing);
+        assert_eq!(gamestate.to_move, White);
+    }
+
+    #[test]
+    pub fn resume() {
+        let gamestate = play(GameState::new(), &[(4, 4, Black)]);
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+
+        let gamestate = gamestate.apply_command(GameCommand::Resume).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Playing);
+        assert_eq!(gamestate.to_move, White);
+
+        // A single pass after resuming doesn't end the game
+        let gamestate = gamestate.apply_command(GameCommand::Pass).unwrap();
+        assert_eq!(gamestate.status, GameStatus::Playing);
 
-        return Some(cloned);
+        assert_eq!(
+            gamestate.apply_command(GameCommand::Resume),
+            Err(GoError::NotOver)
+        );
     }
 }
@@ -0,0 +1,299 @@
+use crate::board::IntersectionState;
+use crate::gamestate::{BoardState, GameStatus};
+
+use std::sync::Arc;
+
+/// A position in the game tree, along with the move which lead to it
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameNode {
+    pub boardstate: BoardState,
+    /// The player to move in this position
+    ///
+    /// While the node is the current one, this is stored when leaving it.
+    pub to_move: IntersectionState,
+    /// Whether the game is still going in this position, stored like to_move
+    pub status: GameStatus,
+    pub comment: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+impl GameNode {
+    pub fn new(boardstate: BoardState, to_move: IntersectionState, status: GameStatus) -> Self {
+        GameNode {
+            boardstate,
+            to_move,
+            status,
+            comment: String::new(),
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the index of the node this one follows, or None for the root
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the indices of the nodes which follow this one.
+    ///
+    /// The first one continues the main line, the others are variations.
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+}
+
+/// A tree of positions, where every position may be followed by several variations.
+///
+/// Nodes are kept in a flat list and refer to each other by index; the root is at index 0.
+/// They are shared between clones of the tree, and only copied when one of the clones changes them,
+/// so cloning a game to apply a move to it stays cheap however long the game is.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameTree {
+    nodes: Vec<Arc<GameNode>>,
+    /// The index of the node we are at
+    current: usize,
+}
+
+impl GameTree {
+    pub const ROOT: usize = 0;
+
+    pub fn new(root: GameNode) -> Self {
+        GameTree {
+            nodes: vec![Arc::new(root)],
+            current: Self::ROOT,
+        }
+    }
+
+    /// Returns the node at the given index
+    pub fn node(&self, index: usize) -> Option<&GameNode> {
+        self.nodes.get(index).map(Arc::as_ref)
+    }
+
+    /// Returns the node at the given index mutably
+    pub fn node_mut(&mut self, index: usize) -> Option<&mut GameNode> {
+        self.nodes.get_mut(index).map(Arc::make_mut)
+    }
+
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always false, since a tree at least has a root
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the index of the node we are at
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn current_node(&self) -> &GameNode {
+        &self.nodes[self.current]
+    }
+
+    pub fn current_node_mut(&mut self) -> &mut GameNode {
+        Arc::make_mut(&mut self.nodes[self.current])
+    }
+
+    /// Moves to the node at the given index, returning false if there is no such node
+    pub fn set_current(&mut self, index: usize) -> bool {
+        if index >= self.nodes.len() {
+            return false;
+        }
+
+        self.current = index;
+        true
+    }
+
+    /// Adds a node after the current one and moves to it, returning its index
+    pub fn add_child(&mut self, mut node: GameNode) -> usize {
+        let index = self.nodes.len();
+
+        node.parent = Some(self.current);
+        node.children.clear();
+
+        self.nodes.push(Arc::new(node));
+        Arc::make_mut(&mut self.nodes[self.current])
+            .children
+            .push(index);
+        self.current = index;
+
+        index
+    }
+
+    /// Returns the indices of the nodes from the root up to and including the given one
+    pub fn path_to(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![index];
+        let mut node = index;
+
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(parent);
+            node = parent;
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Returns the indices of the nodes from the root up to and including the current one
+    pub fn path(&self) -> Vec<usize> {
+        self.path_to(self.current)
+    }
+
+    /// Returns the current line: the path to the current node, followed by the main line after it
+    pub fn line(&self) -> Vec<usize> {
+        let mut line = self.path();
+        let mut node = self.current;
+
+        while let Some(&child) = self.nodes[node].children.first() {
+            line.push(child);
+            node = child;
+        }
+
+        line
+    }
+
+    /// Returns the index of the last node of the main line after the given one
+    pub fn main_line_end(&self, index: usize) -> usize {
+        let mut node = index;
+
+        while let Some(&child) = self.nodes[node].children.first() {
+            node = child;
+        }
+
+        node
+    }
+
+    /// Makes the line leading to the given node the main line, at every branch on the way
+    pub fn promote(&mut self, index: usize) {
+        let mut node = index;
+
+        while let Some(parent) = self.nodes[node].parent {
+            let siblings = &mut Arc::make_mut(&mut self.nodes[parent]).children;
+
+            if let Some(position) = siblings.iter().position(|&child| child == node) {
+                siblings.remove(position);
+                siblings.insert(0, node);
+            }
+
+            node = parent;
+        }
+    }
+
+    /// Removes the given node, along with everything following it.
+    ///
+    /// The remaining nodes are moved together, so indices of later nodes change.
+    /// If the current node is removed, we move to the parent of the removed one.
+    pub fn remove(&mut self, index: usize) {
+        let Some(parent) = self.nodes.get(index).and_then(|node| node.parent) else {
+            // The root can't be removed
+            return;
+        };
+
+        let mut removed = vec![false; self.nodes.len()];
+        let mut to_remove = vec![index];
+
+        while let Some(node) = to_remove.pop() {
+            removed[node] = true;
+            to_remove.extend_from_slice(&self.nodes[node].children);
+        }
+
+        if removed[self.current] {
+            self.current = parent;
+        }
+
+        Arc::make_mut(&mut self.nodes[parent])
+            .children
+            .retain(|&child| child != index);
+
+        // Where every kept node ends up
+        let mut new_indices = vec![0; self.nodes.len()];
+        let mut next_index = 0;
+
+        for (node, new_index) in new_indices.iter_mut().enumerate() {
+            if !removed[node] {
+                *new_index = next_index;
+                next_index += 1;
+            }
+        }
+
+        self.nodes = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .enumerate()
+            .filter(|(node, _)| !removed[*node])
+            .map(|(_, mut node)| {
+                let node_data = Arc::make_mut(&mut node);
+                node_data.parent = node_data.parent.map(|parent| new_indices[parent]);
+                for child in node_data.children.iter_mut() {
+                    *child = new_indices[*child];
+                }
+                node
+            })
+            .collect();
+
+        self.current = new_indices[self.current];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameNode, GameTree};
+    use crate::board::IntersectionState::{Black, White};
+    use crate::gamestate::{BoardState, GameStatus};
+
+    fn node(to_move: crate::board::IntersectionState) -> GameNode {
+        GameNode::new(BoardState::new(), to_move, GameStatus::Playing)
+    }
+
+    #[test]
+    pub fn variations() {
+        let mut tree = GameTree::new(node(Black));
+
+        let main = tree.add_child(node(White));
+        let main_next = tree.add_child(node(Black));
+
+        tree.set_current(GameTree::ROOT);
+        let variation = tree.add_child(node(White));
+        let variation_next = tree.add_child(node(Black));
+
+        assert_eq!(tree.current_node().parent(), Some(variation));
+        assert_eq!(
+            tree.node(GameTree::ROOT).unwrap().children(),
+            &[main, variation]
+        );
+        assert_eq!(tree.path(), vec![GameTree::ROOT, variation, variation_next]);
+        assert_eq!(tree.main_line_end(GameTree::ROOT), main_next);
+
+        tree.promote(variation_next);
+
+        assert_eq!(
+            tree.node(GameTree::ROOT).unwrap().children(),
+            &[variation, main]
+        );
+        assert_eq!(tree.main_line_end(GameTree::ROOT), variation_next);
+    }
+
+    #[test]
+    pub fn remove() {
+        let mut tree = GameTree::new(node(Black));
+
+        let removed = tree.add_child(node(White));
+        tree.add_child(node(Black));
+
+        tree.set_current(GameTree::ROOT);
+        tree.add_child(node(White));
+        let kept = tree.add_child(node(Black));
+        tree.set_current(removed);
+
+        tree.remove(removed);
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.current(), GameTree::ROOT);
+        assert_eq!(tree.line(), vec![GameTree::ROOT, 1, 2]);
+        assert_eq!(tree.node(2).unwrap().parent(), Some(1));
+        assert!(kept > 2);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::board::IntersectionState;
+
+/// A command of the Go Text Protocol, like "12 play b D4"
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GtpCommand {
+    /// The optional id, which is repeated in the response
+    pub id: Option<u32>,
+    pub name: String,
+    pub arguments: Vec<String>,
+}
+
+impl GtpCommand {
+    /// Parses a line of GTP input, returning None for lines without a command.
+    ///
+    /// Control characters and comments are removed first, as described in the protocol.
+    pub fn parse(line: &str) -> Option<GtpCommand> {
+        let line: String = line
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter_map(|c| match c {
+                '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+
+        let mut words = line.split_whitespace();
+        let mut first = words.next()?;
+
+        let id = first.parse::<u32>().ok();
+        if id.is_some() {
+            first = words.next()?;
+        }
+
+        Some(GtpCommand {
+            id,
+            name: first.to_lowercase(),
+            arguments: words.map(str::to_string).collect(),
+        })
+    }
+}
+
+impl std::fmt::Display for GtpCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(id) = self.id {
+            write!(f, "{} ", id)?;
+        }
+
+        write!(f, "{}", self.name)?;
+
+        for argument in self.arguments.iter() {
+            write!(f, " {}", argument)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats the response to a command, which is either a result or an error message
+pub fn format_response(id: Option<u32>, response: Result<String, String>) -> String {
+    let (status, text) = match response {
+        Ok(result) => ('=', result),
+        Err(message) => ('?', message),
+    };
+
+    let id = id.map(|id| id.to_string()).unwrap_or_default();
+
+    if text.is_empty() {
+        format!("{}{}\n\n", status, id)
+    } else {
+        format!("{}{} {}\n\n", status, id, text)
+    }
+}
+
+/// Parses a GTP colour, like "b" or "white"
+pub fn parse_color(color: &str) -> Option<IntersectionState> {
+    match color.to_lowercase().as_str() {
+        "b" | "black" => Some(IntersectionState::Black),
+        "w" | "white" => Some(IntersectionState::White),
+        _ => None,
+    }
+}
+
+/// Formats a colour the way GTP responses write it
+pub fn format_color(color: IntersectionState) -> &'static str {
+    match color {
+        IntersectionState::White => "white",
+        _ => "black",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GtpCommand;
+    use crate::board::IntersectionState::Black;
+
+    #[test]
+    pub fn parse_command() {
+        assert_eq!(
+            GtpCommand::parse("12 play\tB d4 # a comment\r"),
+            Some(GtpCommand {
+                id: Some(12),
+                name: String::from("play"),
+                arguments: vec![String::from("B"), String::from("d4")],
+            })
+        );
+        assert_eq!(GtpCommand::parse("  # only a comment"), None);
+        assert_eq!(
+            GtpCommand::parse("genmove b").unwrap().to_string(),
+            "genmove b"
+        );
+        assert_eq!(super::parse_color("B"), Some(Black));
+    }
+
+    #[test]
+    pub fn responses() {
+        assert_eq!(
+            super::format_response(Some(3), Ok(String::from("2"))),
+            "=3 2\n\n"
+        );
+        assert_eq!(super::format_response(None, Ok(String::new())), "=\n\n");
+        assert_eq!(
+            super::format_response(None, Err(String::from("illegal move"))),
+            "? illegal move\n\n"
+        );
+    }
+}
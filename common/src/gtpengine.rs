@@ -0,0 +1,488 @@
+use crate::board::IntersectionState;
+use crate::coordinates;
+use crate::errors::GoError;
+use crate::gamecommand::GameCommand;
+use crate::gamestate::GameState;
+use crate::gtp;
+use crate::player::Player;
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// How long we wait for a response by default; engines can think for a while
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A move as GTP describes it: a player, and a position or None for a pass
+type GtpMove = (IntersectionState, Option<(usize, usize)>);
+
+/// A GTP engine, such as GNU Go or KataGo, running as a child process which we control.
+///
+/// Keeps track of the moves the engine has been told about, to keep its board in sync with a game.
+pub struct GtpEngine {
+    /// The engine's name, as given by the name command, or the program
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines the engine writes to stdout, read on another thread so we can time out
+    lines: Receiver<String>,
+    next_id: u32,
+    /// How long to wait for each response
+    pub timeout: Duration,
+    /// The board size the engine is set to, if we've set it yet
+    size: Option<usize>,
+    komi: Option<f32>,
+    /// The moves played on the engine's board, in order
+    played: Vec<GtpMove>,
+}
+
+impl GtpEngine {
+    /// Starts an engine from a command line, like ["gnugo", "--mode", "gtp"]
+    pub fn spawn(command_line: &[String]) -> Result<Self, GoError> {
+        let program = command_line.first().cloned().unwrap_or_default();
+        let not_started = |message: String| GoError::EngineNotStarted {
+            program: program.clone(),
+            message,
+        };
+
+        let mut child = Command::new(&program)
+            .args(&command_line[1.min(command_line.len())..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| not_started(e.to_string()))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or(not_started(String::from("no stdin")))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(not_started(String::from("no stdout")))?;
+
+        let (sender, lines) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if sender
+                    .send(line.trim_end_matches('\r').to_string())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = GtpEngine {
+            name: program.clone(),
+            child,
+            stdin,
+            lines,
+            next_id: 1,
+            timeout: DEFAULT_TIMEOUT,
+            size: None,
+            komi: None,
+            played: Vec::new(),
+        };
+
+        // Engines don't have to support the name command, so fall back to the program
+        if let Ok(name) = engine.send("name", &[]) {
+            if !name.is_empty() {
+                engine.name = name;
+            }
+        }
+
+        Ok(engine)
+    }
+
+    /// Returns an error describing why the engine stopped
+    fn crashed(&mut self) -> GoError {
+        let message = match self.child.try_wait() {
+            Ok(Some(status)) => status.to_string(),
+            _ => String::from("it closed its output"),
+        };
+
+        GoError::EngineCrashed { message }
+    }
+
+    /// Sends a command and waits for the response, returning its text
+    pub fn send(&mut self, name: &str, arguments: &[&str]) -> Result<String, GoError> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let command = gtp::GtpCommand {
+            id: Some(id),
+            name: name.to_string(),
+            arguments: arguments.iter().map(|a| a.to_string()).collect(),
+        };
+
+        if writeln!(self.stdin, "{}", command).is_err() || self.stdin.flush().is_err() {
+            return Err(self.crashed());
+        }
+
+        let mut response: Option<(bool, String)> = None;
+        // Whether we're in a late response to an earlier command, which timed out
+        let mut stale = false;
+
+        loop {
+            let line = match self.lines.recv_timeout(self.timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    // The engine may still act on the command, so its board can't be trusted anymore
+                    self.size = None;
+
+                    return Err(GoError::EngineTimeout {
+                        command: command.to_string(),
+                        seconds: self.timeout.as_secs(),
+                    });
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(self.crashed()),
+            };
+
+            if stale {
+                stale = !line.trim().is_empty();
+                continue;
+            }
+
+            match &mut response {
+                // Skip anything before the response starts
+                None => {
+                    let success = match line.chars().next() {
+                        Some('=') => true,
+                        Some('?') => false,
+                        _ => continue,
+                    };
+
+                    // The id, if the engine repeats it, comes right after the status
+                    let rest = &line[1..];
+                    let id_length =
+                        rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                    let (response_id, text) = rest.split_at(id_length);
+
+                    // Skip late responses, along with the lines that follow them
+                    if response_id
+                        .parse::<u32>()
+                        .is_ok_and(|response_id| response_id < id)
+                    {
+                        stale = true;
+                        continue;
+                    }
+
+                    if !response_id.is_empty() && response_id != id.to_string() {
+                        return Err(GoError::EngineRejected {
+                            command: command.to_string(),
+                            message: format!("response has the wrong id: \"{}\"", line),
+                        });
+                    }
+
+                    response = Some((success, text.trim().to_string()));
+                }
+                // An empty line ends the response
+                Some((success, text)) => {
+                    if line.trim().is_empty() {
+                        if !*success {
+                            return Err(GoError::EngineRejected {
+                                command: command.to_string(),
+                                message: text.clone(),
+                            });
+                        }
+                        return Ok(text.clone());
+                    }
+
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                    text.push_str(&line);
+                }
+            }
+        }
+    }
+
+    /// Tells the engine to play a move
+    fn play(&mut self, gtp_move: GtpMove, size: usize) -> Result<(), GoError> {
+        let (player, position) = gtp_move;
+
+        self.send(
+            "play",
+            &[
+                gtp::format_color(player),
+                &coordinates::format_vertex(position, size),
+            ],
+        )?;
+        self.played.push(gtp_move);
+        Ok(())
+    }
+
+    /// Brings the engine's board to the position the game is at.
+    ///
+    /// Moves the engine already knows are kept; if the game went another way, the board is cleared and replayed.
+    pub fn sync(&mut self, gamestate: &GameState) -> Result<(), GoError> {
+        let board = &gamestate.current().board;
+        let size = board.size();
+
+        if !board.is_square() {
+            return Err(GoError::EngineRejected {
+                command: String::from("boardsize"),
+                message: String::from("GTP only supports square boards"),
+            });
+        }
+
+        let moves = gtp_moves(gamestate)?;
+
+        if self.size != Some(size) {
+            self.send("boardsize", &[&size.to_string()])?;
+            self.send("clear_board", &[])?;
+            self.size = Some(size);
+            self.played.clear();
+        }
+
+        if self.komi != Some(gamestate.ruleset.komi) {
+            self.send("komi", &[&gamestate.ruleset.komi.to_string()])?;
+            self.komi = Some(gamestate.ruleset.komi);
+        }
+
+        let mut known = self
+            .played
+            .iter()
+            .zip(moves.iter())
+            .take_while(|(played, gtp_move)| played == gtp_move)
+            .count();
+
+        if known < self.played.len() {
+            self.send("clear_board", &[])?;
+            self.played.clear();
+            known = 0;
+        }
+
+        for &gtp_move in &moves[known..] {
+            self.play(gtp_move, size)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Player for GtpEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Asks the engine for a move of the player to move.
+    ///
+    /// The move is checked with our rules, so illegal replies are errors.
+    fn genmove(&mut self, gamestate: &GameState) -> Result<GameCommand, GoError> {
+        self.sync(gamestate)?;
+
+        let player = gamestate.to_move;
+        let size = gamestate.current().board.size();
+        let reply = self.send("genmove", &[gtp::format_color(player)])?;
+
+        let illegal = |reason: String| GoError::IllegalEngineMove {
+            reply: reply.clone(),
+            reason,
+        };
+
+        let (command, position) = if reply.eq_ignore_ascii_case("resign") {
+            (GameCommand::Resign(player), None)
+        } else {
+            match coordinates::parse_vertex(&reply, size).map_err(|e| illegal(e.to_string()))? {
+                Some((x, y)) => (GameCommand::Set(x, y, player), Some((x, y))),
+                None => (GameCommand::Pass, None),
+            }
+        };
+
+        gamestate
+            .apply_command(command)
+            .map_err(|e| illegal(e.to_string()))?;
+
+        if command != GameCommand::Resign(player) {
+            self.played.push((player, position));
+        }
+
+        Ok(command)
+    }
+}
+
+impl Drop for GtpEngine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Returns the moves leading to the current position of a game, as GTP plays them.
+///
+/// Setup stones are played as moves of their colour, since GTP has no setup.
+fn gtp_moves(gamestate: &GameState) -> Result<Vec<GtpMove>, GoError> {
+    let tree = gamestate.tree();
+    let mut moves = Vec::new();
+
+    for node in tree.path() {
+        let node_data = tree.node(node).unwrap();
+
+        match node_data.boardstate.last_move {
+            Some(GameCommand::Set(x, y, player)) => moves.push((player, Some((x, y)))),
+            Some(GameCommand::Setup(x, y, player)) => {
+                if player == IntersectionState::Empty {
+                    return Err(GoError::EngineRejected {
+                        command: String::from("play"),
+                        message: String::from("GTP can't remove stones set up on the board"),
+                    });
+                }
+                moves.push((player, Some((x, y))));
+            }
+            Some(GameCommand::Pass) => {
+                let parent = node_data.parent().unwrap_or_default();
+                moves.push((gamestate.to_move_at(parent), None));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GtpEngine;
+    use crate::board::IntersectionState::{Black, White};
+    use crate::errors::GoError;
+    use crate::gamecommand::GameCommand;
+    use crate::gamestate::GameState;
+    use crate::player::Player;
+    use std::time::Duration;
+
+    /// Starts the mock engine from the testdata directory with the given arguments
+    fn mock_engine(arguments: &[&str]) -> GtpEngine {
+        let mut command_line = vec![
+            String::from("sh"),
+            format!("{}/testdata/mock_engine.sh", env!("CARGO_MANIFEST_DIR")),
+        ];
+        command_line.extend(arguments.iter().map(|a| a.to_string()));
+
+        let mut engine = GtpEngine::spawn(&command_line).unwrap();
+        engine.timeout = Duration::from_secs(2);
+        engine
+    }
+
+    #[test]
+    pub fn plays_against_engine() {
+        let mut engine = mock_engine(&["normal", "E5", "pass"]);
+        assert_eq!(engine.name(), "mock");
+
+        let gamestate = GameState::new()
+            .apply_command(GameCommand::Set(2, 2, Black))
+            .unwrap();
+
+        let command = engine.genmove(&gamestate).unwrap();
+        assert_eq!(command, GameCommand::Set(4, 4, White));
+
+        let gamestate = gamestate.apply_command(command).unwrap();
+        assert_eq!(
+            gamestate.current().board.get_intersection(4, 4),
+            Some(White)
+        );
+        assert_eq!(
+            engine.played,
+            vec![(Black, Some((2, 2))), (White, Some((4, 4)))]
+        );
+
+        // Going another way replays the game on a cleared board
+        let gamestate = gamestate
+            .apply_command(GameCommand::Undo)
+            .unwrap()
+            .apply_command(GameCommand::Pass)
+            .unwrap()
+            .apply_command(GameCommand::Pass)
+            .unwrap();
+
+        engine.sync(&gamestate).unwrap();
+        assert_eq!(
+            engine.played,
+            vec![(Black, Some((2, 2))), (White, None), (Black, None)]
+        );
+
+        let gamestate = gamestate.apply_command(GameCommand::Resume).unwrap();
+        assert_eq!(engine.genmove(&gamestate), Ok(GameCommand::Pass));
+    }
+
+    #[test]
+    pub fn illegal_reply() {
+        let mut engine = mock_engine(&["normal", "C7"]);
+
+        let gamestate = GameState::new()
+            .apply_command(GameCommand::Set(2, 2, Black))
+            .unwrap();
+
+        assert_eq!(
+            engine.genmove(&gamestate).err(),
+            Some(GoError::IllegalEngineMove {
+                reply: String::from("C7"),
+                reason: GoError::IntersectionOccupied {
+                    x: 2,
+                    y: 2,
+                    height: 9
+                }
+                .to_string()
+            })
+        );
+    }
+
+    #[test]
+    pub fn crash_and_timeout() {
+        let mut engine = mock_engine(&["crash"]);
+        assert!(matches!(
+            engine.genmove(&GameState::new()),
+            Err(GoError::EngineCrashed { .. })
+        ));
+
+        let mut engine = mock_engine(&["hang"]);
+        engine.timeout = Duration::from_millis(200);
+        assert!(matches!(
+            engine.genmove(&GameState::new()),
+            Err(GoError::EngineTimeout { .. })
+        ));
+    }
+
+    #[test]
+    pub fn late_reply() {
+        let mut engine = mock_engine(&["slow", "E5", "D4"]);
+        engine.timeout = Duration::from_millis(300);
+
+        let gamestate = GameState::new()
+            .apply_command(GameCommand::Set(2, 2, Black))
+            .unwrap();
+
+        assert!(matches!(
+            engine.genmove(&gamestate),
+            Err(GoError::EngineTimeout { .. })
+        ));
+
+        // The late E5 is skipped, and the engine's board is cleared of it before asking again
+        engine.timeout = Duration::from_secs(2);
+        assert_eq!(
+            engine.genmove(&gamestate),
+            Ok(GameCommand::Set(3, 5, White))
+        );
+        assert_eq!(
+            engine.played,
+            vec![(Black, Some((2, 2))), (White, Some((3, 5)))]
+        );
+    }
+
+    #[test]
+    pub fn missing_engine() {
+        assert!(matches!(
+            GtpEngine::spawn(&[String::from("/nonexistent/engine")]),
+            Err(GoError::EngineNotStarted { .. })
+        ));
+    }
+}
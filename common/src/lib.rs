@@ -1,8 +1,17 @@
 pub mod board;
+pub mod command;
+pub mod coordinates;
 pub mod errors;
 pub mod gamecommand;
 pub mod gamestate;
+pub mod gametree;
+pub mod gtp;
+pub mod gtpengine;
+pub mod marking;
+pub mod mcts;
+pub mod player;
 pub mod rendering;
-
-/// The english alphabet, used instead of numbers for x values
-pub const ASCII: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub mod ruleset;
+pub mod scoring;
+pub mod sgf;
+pub mod zobrist;
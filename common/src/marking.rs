@@ -0,0 +1,118 @@
+use crate::board::{Board, IntersectionState};
+use crate::errors::GoError;
+use crate::gamestate::BoardState;
+use crate::ruleset::Ruleset;
+use crate::scoring::{self, ScoreReport};
+use std::collections::HashSet;
+
+/// Describes the agreement on which stones are dead, after a game has ended by passing.
+///
+/// Stones are marked a whole group at a time, and both players have to accept the marking
+/// before the game can be scored.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DeadStoneMarking {
+    pub dead_stones: HashSet<(usize, usize)>,
+    /// The players who accepted the current marking
+    pub accepted: HashSet<IntersectionState>,
+}
+
+impl DeadStoneMarking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Toggles the group with the stone at (x, y) between dead and alive.
+    ///
+    /// Changing the marking withdraws any previous acceptance.
+    pub fn toggle_group(&mut self, board: &Board, x: usize, y: usize) -> Result<(), GoError> {
+        match board.get_intersection(x, y) {
+            None => {
+                return Err(GoError::InvalidPosition {
+                    x,
+                    y,
+                    width: board.width(),
+                    height: board.height(),
+                })
+            }
+            Some(IntersectionState::Empty) => {
+                return Err(GoError::NoStone {
+                    x,
+                    y,
+                    height: board.height(),
+                })
+            }
+            Some(_) => {}
+        }
+
+        let group = board.find_intersections_in_group(x, y)?;
+
+        if self.dead_stones.contains(&(x, y)) {
+            for stone in group {
+                self.dead_stones.remove(&stone);
+            }
+        } else {
+            self.dead_stones.extend(group);
+        }
+
+        self.accepted.clear();
+
+        Ok(())
+    }
+
+    /// Accepts the current marking on behalf of a player
+    pub fn accept(&mut self, player: IntersectionState) {
+        if player != IntersectionState::Empty {
+            self.accepted.insert(player);
+        }
+    }
+
+    /// Returns whether both players accepted the current marking
+    pub fn is_agreed(&self) -> bool {
+        self.accepted.contains(&IntersectionState::Black)
+            && self.accepted.contains(&IntersectionState::White)
+    }
+
+    /// Scores a boardstate with the current marking
+    pub fn score(&self, boardstate: &BoardState, ruleset: &Ruleset) -> ScoreReport {
+        scoring::score(boardstate, &self.dead_stones, ruleset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeadStoneMarking;
+    use crate::board::{Board, IntersectionState};
+
+    #[test]
+    pub fn toggle_group() {
+        let mut board = Board::new(9);
+        board
+            .set_intersection(2, 2, IntersectionState::White)
+            .unwrap();
+        board
+            .set_intersection(2, 3, IntersectionState::White)
+            .unwrap();
+        board
+            .set_intersection(6, 6, IntersectionState::Black)
+            .unwrap();
+
+        let mut marking = DeadStoneMarking::new();
+
+        marking.toggle_group(&board, 2, 3).unwrap();
+        assert_eq!(marking.dead_stones.len(), 2);
+
+        marking.accept(IntersectionState::Black);
+        marking.accept(IntersectionState::White);
+        assert!(marking.is_agreed());
+
+        // Changing the marking needs a new agreement
+        marking.toggle_group(&board, 6, 6).unwrap();
+        assert_eq!(marking.dead_stones.len(), 3);
+        assert!(!marking.is_agreed());
+
+        marking.toggle_group(&board, 2, 2).unwrap();
+        assert_eq!(marking.dead_stones.len(), 1);
+
+        assert!(marking.toggle_group(&board, 0, 0).is_err());
+    }
+}
@@ -0,0 +1,536 @@
+use crate::board::{Board, IntersectionState};
+use crate::errors::GoError;
+use crate::gamecommand::GameCommand;
+use crate::gamestate::GameState;
+use crate::player::{is_own_eye, Player};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How much the search favours trying less visited moves over the best ones so far
+const EXPLORATION: f32 = 0.8;
+
+/// A move in the search, a position or None for a pass
+type Move = Option<(usize, usize)>;
+
+/// A position in a search or a playout: a board, and the intersection simple ko forbids playing on.
+///
+/// It doesn't keep a history, so superko is left to the real game rules.
+#[derive(Clone, Debug)]
+struct Playout {
+    board: Board,
+    ko: Option<(usize, usize)>,
+}
+
+impl Playout {
+    /// Returns whether a player may sensibly play on an intersection:
+    /// the move has to be legal, and not fill one of the player's own eyes
+    fn is_candidate(&self, x: usize, y: usize, player: IntersectionState) -> bool {
+        self.ko != Some((x, y))
+            && self.board.is_legal(x, y, player)
+            && !is_own_eye(&self.board, x, y, player)
+    }
+
+    /// Plays a move, returning the passes in a row after it
+    fn play_move(&mut self, mv: Move, player: IntersectionState, passes: u8) -> u8 {
+        match mv {
+            Some((x, y)) => {
+                let captured = self.board.play_stone(x, y, player).unwrap_or_default();
+                self.ko = self.board.ko_point(x, y, captured);
+                0
+            }
+            None => {
+                self.ko = None;
+                passes + 1
+            }
+        }
+    }
+
+    /// Returns every move a player could sensibly play, see [Playout::is_candidate]
+    fn candidate_moves(&self, player: IntersectionState) -> Vec<(usize, usize)> {
+        let (width, height) = (self.board.width(), self.board.height());
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_candidate(x, y, player))
+            .collect()
+    }
+
+    /// Picks a random sensible move, or None if only passing is left
+    fn random_move(&self, player: IntersectionState, rng: &mut StdRng) -> Move {
+        let width = self.board.width();
+        let points = width * self.board.height();
+        let start = rng.gen_range(0..points);
+
+        (0..points)
+            .map(|offset| (start + offset) % points)
+            .map(|point| (point % width, point / width))
+            .find(|&(x, y)| self.is_candidate(x, y, player))
+    }
+
+    /// Returns black's area score minus white's, komi included.
+    ///
+    /// Meant for finished playouts, where every empty intersection is surrounded by one colour.
+    fn area_score(&self, komi: f32) -> f32 {
+        let board = &self.board;
+        let mut score = -komi;
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let owner = match board.get_intersection(x, y).unwrap_or_default() {
+                    IntersectionState::Empty => {
+                        let mut colors = board
+                            .adjacent(x, y)
+                            .filter_map(|(nx, ny)| board.get_intersection(nx, ny))
+                            .filter(|&state| state != IntersectionState::Empty);
+
+                        // Empty intersections touching both colours are neutral
+                        match colors.next() {
+                            Some(first) if colors.all(|state| state == first) => first,
+                            _ => IntersectionState::Empty,
+                        }
+                    }
+                    stone => stone,
+                };
+
+                match owner {
+                    IntersectionState::Black => score += 1.0,
+                    IntersectionState::White => score -= 1.0,
+                    IntersectionState::Empty => {}
+                }
+            }
+        }
+
+        score
+    }
+}
+
+/// Returns the intersection simple ko forbids the player to move to play on, if the last move took a ko
+fn current_ko(gamestate: &GameState) -> Option<(usize, usize)> {
+    let boardstate = gamestate.current();
+    let Some(GameCommand::Set(x, y, player)) = boardstate.last_move else {
+        return None;
+    };
+
+    let tree = gamestate.tree();
+    let before = &tree.node(tree.current_node().parent()?)?.boardstate;
+
+    let captured = match player {
+        IntersectionState::White => boardstate
+            .white_prisoners
+            .saturating_sub(before.white_prisoners),
+        _ => boardstate
+            .black_prisoners
+            .saturating_sub(before.black_prisoners),
+    };
+
+    boardstate.board.ko_point(x, y, captured)
+}
+
+/// How long the search runs for each move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchBudget {
+    /// A number of playouts, split between the threads
+    Playouts(usize),
+    /// A time limit
+    Time(Duration),
+}
+
+/// A position in the search tree
+struct SearchNode {
+    /// The move leading here
+    mv: Move,
+    /// The player who played the move leading here
+    mover: IntersectionState,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves which don't have a child yet
+    untried: Vec<Move>,
+    visits: u32,
+    /// Playouts won by the mover
+    wins: f32,
+}
+
+/// The statistics of one move at the root, after a search
+#[derive(Clone, Copy, Debug)]
+struct RootMove {
+    mv: Move,
+    visits: u32,
+    wins: f32,
+}
+
+/// The position a search starts from
+#[derive(Clone)]
+struct SearchRoot {
+    position: Playout,
+    to_move: IntersectionState,
+    /// Passes played right before the position
+    passes: u8,
+    komi: f32,
+}
+
+impl SearchRoot {
+    /// Returns the moves a node may be expanded with; after two passes the game is over
+    fn untried_moves(position: &Playout, player: IntersectionState, passes: u8) -> Vec<Move> {
+        if passes >= 2 {
+            return Vec::new();
+        }
+
+        let mut moves: Vec<Move> = position
+            .candidate_moves(player)
+            .into_iter()
+            .map(Some)
+            .collect();
+        moves.push(None);
+        moves
+    }
+
+    /// Starts a search from the current position of a game
+    fn from_gamestate(gamestate: &GameState) -> Self {
+        let boardstate = gamestate.current();
+
+        SearchRoot {
+            position: Playout {
+                board: boardstate.board.clone(),
+                ko: current_ko(gamestate),
+            },
+            to_move: gamestate.to_move,
+            passes: u8::from(boardstate.last_move == Some(GameCommand::Pass)),
+            komi: gamestate.ruleset.komi,
+        }
+    }
+
+    /// Searches the tree from this position until the budget runs out.
+    ///
+    /// Returns the statistics of every move tried at the root.
+    fn search(&self, budget: SearchBudget, rng: &mut StdRng) -> Vec<RootMove> {
+        let start = Instant::now();
+        let board = &self.position.board;
+        let max_moves = board.width() * board.height() * 3;
+
+        let mut nodes = vec![SearchNode {
+            mv: None,
+            mover: self.to_move.opposite(),
+            parent: None,
+            children: Vec::new(),
+            untried: Self::untried_moves(&self.position, self.to_move, self.passes),
+            visits: 0,
+            wins: 0.0,
+        }];
+
+        let mut playouts = 0;
+
+        loop {
+            let done = match budget {
+                SearchBudget::Playouts(limit) => playouts >= limit,
+                SearchBudget::Time(limit) => start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+            playouts += 1;
+
+            let mut position = self.position.clone();
+            let mut color = self.to_move;
+            let mut passes = self.passes;
+            let mut node = 0;
+
+            // Go down the tree, picking children by their upper confidence bound
+            while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+                let log_visits = (nodes[node].visits as f32).ln();
+
+                node = *nodes[node]
+                    .children
+                    .iter()
+                    .max_by(|&&a, &&b| {
+                        let bound = |child: &SearchNode| {
+                            child.wins / child.visits as f32
+                                + EXPLORATION * (log_visits / child.visits as f32).sqrt()
+                        };
+                        bound(&nodes[a]).total_cmp(&bound(&nodes[b]))
+                    })
+                    .unwrap();
+
+                passes = position.play_move(nodes[node].mv, color, passes);
+                color = color.opposite();
+            }
+
+            // Add one untried move to the tree
+            if !nodes[node].untried.is_empty() {
+                let choice = rng.gen_range(0..nodes[node].untried.len());
+                let mv = nodes[node].untried.swap_remove(choice);
+
+                passes = position.play_move(mv, color, passes);
+
+                let child = nodes.len();
+                nodes.push(SearchNode {
+                    mv,
+                    mover: color,
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: Self::untried_moves(&position, color.opposite(), passes),
+                    visits: 0,
+                    wins: 0.0,
+                });
+                nodes[node].children.push(child);
+
+                node = child;
+                color = color.opposite();
+            }
+
+            // Play the rest of the game randomly
+            let mut moves = 0;
+
+            while passes < 2 && moves < max_moves {
+                let mv = position.random_move(color, rng);
+                passes = position.play_move(mv, color, passes);
+                color = color.opposite();
+                moves += 1;
+            }
+
+            let winner = if position.area_score(self.komi) > 0.0 {
+                IntersectionState::Black
+            } else {
+                IntersectionState::White
+            };
+
+            let mut current = Some(node);
+
+            while let Some(index) = current {
+                nodes[index].visits += 1;
+                if nodes[index].mover == winner {
+                    nodes[index].wins += 1.0;
+                }
+                current = nodes[index].parent;
+            }
+        }
+
+        nodes[0]
+            .children
+            .iter()
+            .map(|&child| RootMove {
+                mv: nodes[child].mv,
+                visits: nodes[child].visits,
+                wins: nodes[child].wins,
+            })
+            .collect()
+    }
+}
+
+/// Picks moves with Monte Carlo tree search, using the UCT formula and random playouts.
+///
+/// With several threads, every thread searches its own tree and their results are added up.
+pub struct MctsPlayer {
+    /// How long to search for every move
+    pub budget: SearchBudget,
+    /// How many threads to search with, at least one
+    pub threads: usize,
+    rng: StdRng,
+}
+
+impl MctsPlayer {
+    pub fn new(budget: SearchBudget) -> Self {
+        MctsPlayer {
+            budget,
+            threads: 1,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates a player which always picks the same moves in the same games, when using one thread
+    pub fn with_seed(budget: SearchBudget, seed: u64) -> Self {
+        MctsPlayer {
+            budget,
+            threads: 1,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Searches a position, returning the moves tried at the root with the most visited first
+    fn search(&mut self, root: &SearchRoot) -> Vec<RootMove> {
+        let threads = self.threads.max(1);
+
+        let budget = match self.budget {
+            SearchBudget::Playouts(playouts) => SearchBudget::Playouts(playouts.div_ceil(threads)),
+            time => time,
+        };
+
+        let mut rngs: Vec<StdRng> = (0..threads)
+            .map(|_| StdRng::seed_from_u64(self.rng.gen()))
+            .collect();
+
+        let results: Vec<Vec<RootMove>> = if threads == 1 {
+            vec![root.search(budget, &mut rngs[0])]
+        } else {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = rngs
+                    .iter_mut()
+                    .map(|rng| scope.spawn(move || root.search(budget, rng)))
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        // A search thread only panics on a bug, which shouldn't be mistaken for an empty search
+                        handle
+                            .join()
+                            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                    })
+                    .collect()
+            })
+        };
+
+        let mut merged: HashMap<Move, RootMove> = HashMap::new();
+
+        for root_move in results.into_iter().flatten() {
+            let total = merged.entry(root_move.mv).or_insert(RootMove {
+                mv: root_move.mv,
+                visits: 0,
+                wins: 0.0,
+            });
+            total.visits += root_move.visits;
+            total.wins += root_move.wins;
+        }
+
+        let mut moves: Vec<RootMove> = merged.into_values().collect();
+        moves.sort_by(|a, b| {
+            b.visits
+                .cmp(&a.visits)
+                .then(b.wins.total_cmp(&a.wins))
+                .then(a.mv.cmp(&b.mv))
+        });
+        moves
+    }
+}
+
+impl Player for MctsPlayer {
+    fn name(&self) -> String {
+        String::from("MCTS bot")
+    }
+
+    fn genmove(&mut self, gamestate: &GameState) -> Result<GameCommand, GoError> {
+        if gamestate.status.is_over() {
+            return Err(GoError::GameOver);
+        }
+
+        let root = SearchRoot::from_gamestate(gamestate);
+        let player = gamestate.to_move;
+
+        // The search only knows simple ko, so the best move might still break the real rules
+        for root_move in self.search(&root) {
+            let command = match root_move.mv {
+                Some((x, y)) => GameCommand::Set(x, y, player),
+                None => GameCommand::Pass,
+            };
+
+            if gamestate.apply_command(command).is_ok() {
+                return Ok(command);
+            }
+        }
+
+        Ok(GameCommand::Pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MctsPlayer, Playout, SearchBudget, SearchRoot};
+    use crate::board::{Board, IntersectionState::*};
+    use crate::gamecommand::GameCommand;
+    use crate::gamestate::GameState;
+    use crate::player::Player;
+
+    #[test]
+    pub fn playout() {
+        // A ko: black takes at (2, 1), and white can't take back right away
+        let mut position = Playout {
+            board: Board::new(5),
+            ko: None,
+        };
+        for (x, y) in [(1, 0), (0, 1), (1, 2)] {
+            position.play_move(Some((x, y)), Black, 0);
+        }
+        for (x, y) in [(2, 0), (3, 1), (2, 2), (1, 1)] {
+            position.play_move(Some((x, y)), White, 0);
+        }
+
+        position.play_move(Some((2, 1)), Black, 0);
+        assert_eq!(position.board.get_intersection(1, 1), Some(Empty));
+        assert_eq!(position.ko, Some((1, 1)));
+        assert!(!position.is_candidate(1, 1, White));
+        assert!(!position.candidate_moves(White).contains(&(1, 1)));
+
+        // Passing lifts the ko
+        assert_eq!(position.play_move(None, White, 0), 1);
+        assert!(position.is_candidate(1, 1, White));
+
+        // Black's own eye in the corner isn't worth filling
+        assert!(position.board.is_legal(0, 0, Black));
+        assert!(!position.is_candidate(0, 0, Black));
+
+        let mut position = Playout {
+            board: Board::new(3),
+            ko: None,
+        };
+        for y in 0..3 {
+            position.play_move(Some((1, y)), Black, 0);
+        }
+        assert_eq!(position.area_score(0.5), 8.5);
+    }
+
+    #[test]
+    pub fn root_ko() {
+        let mut gamestate = GameState::with_board(Board::new(5));
+        for (x, y, player) in [
+            (1, 0, Black),
+            (2, 0, White),
+            (0, 1, Black),
+            (3, 1, White),
+            (1, 2, Black),
+            (2, 2, White),
+            (4, 4, Black),
+            (1, 1, White),
+        ] {
+            gamestate = gamestate
+                .apply_command(GameCommand::Set(x, y, player))
+                .unwrap();
+        }
+
+        // The ko isn't taken yet
+        assert_eq!(SearchRoot::from_gamestate(&gamestate).position.ko, None);
+
+        let gamestate = gamestate
+            .apply_command(GameCommand::Set(2, 1, Black))
+            .unwrap();
+        let root = SearchRoot::from_gamestate(&gamestate);
+        assert_eq!(root.position.ko, Some((1, 1)));
+        assert_eq!(root.to_move, White);
+    }
+
+    #[test]
+    pub fn takes_the_center() {
+        let gamestate = GameState::with_board(Board::new(3));
+        let mut player = MctsPlayer::with_seed(SearchBudget::Playouts(2000), 3);
+
+        assert_eq!(
+            player.genmove(&gamestate),
+            Ok(GameCommand::Set(1, 1, Black))
+        );
+    }
+
+    #[test]
+    pub fn threaded_search() {
+        let mut gamestate = GameState::with_board(Board::new(5));
+        let mut player = MctsPlayer::with_seed(SearchBudget::Playouts(400), 5);
+        player.threads = 4;
+
+        for _ in 0..6 {
+            let command = player.genmove(&gamestate).unwrap();
+            gamestate = gamestate.apply_command(command).unwrap();
+        }
+
+        assert_eq!(gamestate.move_number(), 6);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::board::{Board, IntersectionState};
+use crate::errors::GoError;
+use crate::gamecommand::GameCommand;
+use crate::gamestate::GameState;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Something which picks moves on its own, like a bot or an engine
+pub trait Player {
+    /// Returns the name shown for this player
+    fn name(&self) -> String;
+
+    /// Picks a move for the player to move in the given game.
+    ///
+    /// Returns a Set, Pass or Resign command, which is legal to apply to the game.
+    fn genmove(&mut self, gamestate: &GameState) -> Result<GameCommand, GoError>;
+}
+
+/// Returns whether an empty intersection is a single-point eye of the given player.
+///
+/// All of its neighbours need to be the player's stones, and on the diagonals the opponent may
+/// have at most one stone, or none on the edge; otherwise the eye could be false.
+pub fn is_own_eye(board: &Board, x: usize, y: usize, player: IntersectionState) -> bool {
+    if board.get_intersection(x, y) != Some(IntersectionState::Empty) {
+        return false;
+    }
+
+    if board
+        .adjacent(x, y)
+        .any(|(nx, ny)| board.get_intersection(nx, ny) != Some(player))
+    {
+        return false;
+    }
+
+    let mut diagonals = 0;
+    let mut opponent_diagonals = 0;
+
+    for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+        let (Some(dx), Some(dy)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+            continue;
+        };
+
+        match board.get_intersection(dx, dy) {
+            Some(state) => {
+                diagonals += 1;
+                if state == player.opposite() {
+                    opponent_diagonals += 1;
+                }
+            }
+            None => continue,
+        }
+    }
+
+    if diagonals < 4 {
+        opponent_diagonals == 0
+    } else {
+        opponent_diagonals < 2
+    }
+}
+
+/// Plays uniformly random legal moves.
+///
+/// It doesn't fill its own single-point eyes, and passes when no other move is left.
+pub struct RandomPlayer {
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+    pub fn new() -> Self {
+        RandomPlayer {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates a player which always picks the same moves in the same games
+    pub fn with_seed(seed: u64) -> Self {
+        RandomPlayer {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Player for RandomPlayer {
+    fn name(&self) -> String {
+        String::from("Random bot")
+    }
+
+    fn genmove(&mut self, gamestate: &GameState) -> Result<GameCommand, GoError> {
+        if gamestate.status.is_over() {
+            return Err(GoError::GameOver);
+        }
+
+        let player = gamestate.to_move;
+        let board = &gamestate.current().board;
+
+        let mut candidates = Vec::new();
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                if board.get_intersection(x, y) == Some(IntersectionState::Empty)
+                    && !is_own_eye(board, x, y, player)
+                {
+                    candidates.push((x, y));
+                }
+            }
+        }
+
+        candidates.shuffle(&mut self.rng);
+
+        // The first legal candidate is a uniformly random one
+        for (x, y) in candidates {
+            let command = GameCommand::Set(x, y, player);
+
+            if gamestate.apply_command(command).is_ok() {
+                return Ok(command);
+            }
+        }
+
+        Ok(GameCommand::Pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Player, RandomPlayer};
+    use crate::board::IntersectionState::{Black, White};
+    use crate::gamecommand::GameCommand;
+    use crate::gamestate::GameState;
+
+    /// Sets up a board with the given rows, X being black and O being white
+    fn setup_rows(rows: &[&str]) -> GameState {
+        let mut gamestate = GameState::with_board(crate::board::Board::new(rows.len()));
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().enumerate() {
+                let state = match character {
+                    'X' => Black,
+                    'O' => White,
+                    _ => continue,
+                };
+                gamestate = gamestate
+                    .apply_command(GameCommand::Setup(x, y, state))
+                    .unwrap();
+            }
+        }
+
+        gamestate
+    }
+
+    #[test]
+    pub fn eyes() {
+        let gamestate = setup_rows(&[".X.O.", "XX.O.", "...O.", "OOOOO", "....."]);
+        let board = &gamestate.current().board;
+
+        assert!(super::is_own_eye(board, 0, 0, Black));
+        assert!(!super::is_own_eye(board, 0, 0, White));
+        assert!(!super::is_own_eye(board, 2, 0, Black));
+        assert!(!super::is_own_eye(board, 4, 4, White));
+    }
+
+    #[test]
+    pub fn plays_legal_moves_until_passing() {
+        let mut player = RandomPlayer::with_seed(7);
+        let mut gamestate = GameState::with_board(crate::board::Board::new(5));
+        let mut passes = 0;
+
+        for _ in 0..200 {
+            let command = player.genmove(&gamestate).unwrap();
+
+            if command == GameCommand::Pass {
+                passes += 1;
+            }
+
+            gamestate = gamestate.apply_command(command).unwrap();
+
+            if gamestate.status.is_over() {
+                break;
+            }
+        }
+
+        assert!(gamestate.status.is_over());
+        assert!(passes >= 2);
+    }
+
+    #[test]
+    pub fn leaves_eyes_alone() {
+        // Black's only empty points are its own two eyes
+        let gamestate = setup_rows(&[".X.XO", "XXXXO", "XXXXO", "OOOOO", "O.O.O"]);
+
+        assert_eq!(
+            RandomPlayer::with_seed(1).genmove(&gamestate),
+            Ok(GameCommand::Pass)
+        );
+    }
+}
@@ -1,7 +1,14 @@
 use crate::board::{Board, IntersectionState};
+use crate::coordinates;
+use std::collections::HashSet;
+use std::io::Write;
 
 pub const WHITE_STONE: char = '●';
 pub const BLACK_STONE: char = '○';
+/// Drawn instead of stones which are marked, for example as dead
+pub const MARKED_STONE: char = '×';
+/// Previews a move on boards drawn without colour
+pub const GHOST_STONE: char = '◌';
 
 pub const BOX_TL_CORNER: char = '┌';
 pub const BOX_TR_CORNER: char = '┐';
@@ -22,6 +29,12 @@ pub const ANSI_SET_BG: &str = "42;2;";
 pub const ANSI_RESET: &str = "0m";
 pub const ANSI_WHITE: &str = "37m";
 pub const ANSI_BOLD: &str = "1m";
+/// Swaps the foreground and background colours, to highlight the cursor
+pub const ANSI_REVERSE: &str = "7m";
+pub const ANSI_NO_REVERSE: &str = "27m";
+/// Dims the stone previewed where a move would be played
+pub const ANSI_FAINT: &str = "2m";
+pub const ANSI_NO_FAINT: &str = "22m";
 
 // Color pallete: https://coolors.co/deab2b-c2941e-f3f7f4-000022
 pub const BG_COLOR: &str = "194;171;43m";
@@ -32,90 +45,462 @@ pub const BLACK_COLOR: &str = "0;0;34m";
 // Horizontally we insert a line after each character to show a proper square in the terminal
 pub const BOX_LINE: char = '─';
 
+pub const ASCII_EMPTY: char = '.';
+pub const ASCII_BLACK: char = 'X';
+pub const ASCII_WHITE: char = 'O';
+pub const ASCII_MARKED_BLACK: char = 'x';
+pub const ASCII_MARKED_WHITE: char = 'o';
+pub const ASCII_GHOST: char = '*';
+
+/// Drawn on either side of the cursor by renderers without colours, in place of the margin or lines
+pub const CURSOR_BRACKETS: (char, char) = ('[', ']');
+
 /// Renders a board to stdout.
 pub fn render_board(board: &Board) {
+    render_board_marked(board, &HashSet::new());
+}
+
+/// Renders a board to stdout, drawing the stones at the marked intersections with a cross.
+pub fn render_board_marked(board: &Board, marked: &HashSet<(usize, usize)>) {
+    let overlay = Overlay {
+        marked: marked.clone(),
+        ..Overlay::default()
+    };
+
+    print!("{}", AnsiRenderer.render(board, &overlay));
+}
+
+/// What is drawn over the stones of a board
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Overlay {
+    /// Stones drawn with a cross, for example because they are dead
+    pub marked: HashSet<(usize, usize)>,
+    /// The highlighted intersection
+    pub cursor: Option<(usize, usize)>,
+    /// A dimmed stone previewing a move, drawn if the intersection is empty
+    pub ghost: Option<(usize, usize, IntersectionState)>,
+}
+
+/// Draws boards as text.
+///
+/// All renderers lay boards out the same way: the column labels on top, then a line per row,
+/// starting with a margin, with a character per intersection, another one after it and the row label on the right.
+pub trait Renderer {
+    /// Draws a board with an overlay, ending every line with a line break
+    fn render(&self, board: &Board, overlay: &Overlay) -> String;
+
+    /// Writes a board with an overlay, the way [Renderer::render] draws it
+    fn write_board(
+        &self,
+        out: &mut dyn Write,
+        board: &Board,
+        overlay: &Overlay,
+    ) -> std::io::Result<()> {
+        out.write_all(self.render(board, overlay).as_bytes())
+    }
+}
+
+/// Draws boards in truecolor, with a wooden background and coloured stones
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AnsiRenderer;
+
+/// Draws boards with box drawing characters and circles, without colours or any other escape codes
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct UnicodeRenderer;
+
+/// Draws boards with . for empty intersections, X for black and O for white stones, in plain ASCII
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AsciiRenderer;
+
+/// The names renderers can be chosen by
+pub const RENDERER_NAMES: [&str; 3] = ["ansi", "unicode", "ascii"];
+
+/// Returns the renderer with the given name, ignoring case
+pub fn renderer_from_name(name: &str) -> Option<Box<dyn Renderer>> {
+    match name.to_lowercase().as_str() {
+        "ansi" | "color" | "colour" => Some(Box::new(AnsiRenderer)),
+        "unicode" => Some(Box::new(UnicodeRenderer)),
+        "ascii" => Some(Box::new(AsciiRenderer)),
+        _ => None,
+    }
+}
+
+impl Renderer for AnsiRenderer {
+    fn render(&self, board: &Board, overlay: &Overlay) -> String {
+        let glyphs = Glyphs {
+            black: WHITE_STONE,
+            white: WHITE_STONE,
+            marked_black: MARKED_STONE,
+            marked_white: MARKED_STONE,
+            ghost: WHITE_STONE,
+            empty: None,
+            line: BOX_LINE,
+            cursor: None,
+        };
+
+        draw(board, overlay, &glyphs, true)
+    }
+}
+
+impl Renderer for UnicodeRenderer {
+    fn render(&self, board: &Board, overlay: &Overlay) -> String {
+        let glyphs = Glyphs {
+            black: BLACK_STONE,
+            white: WHITE_STONE,
+            marked_black: MARKED_STONE,
+            marked_white: MARKED_STONE,
+            ghost: GHOST_STONE,
+            empty: None,
+            line: BOX_LINE,
+            cursor: Some(CURSOR_BRACKETS),
+        };
+
+        draw(board, overlay, &glyphs, false)
+    }
+}
+
+impl Renderer for AsciiRenderer {
+    fn render(&self, board: &Board, overlay: &Overlay) -> String {
+        let glyphs = Glyphs {
+            black: ASCII_BLACK,
+            white: ASCII_WHITE,
+            marked_black: ASCII_MARKED_BLACK,
+            marked_white: ASCII_MARKED_WHITE,
+            ghost: ASCII_GHOST,
+            empty: Some(ASCII_EMPTY),
+            line: ' ',
+            cursor: Some(CURSOR_BRACKETS),
+        };
+
+        draw(board, overlay, &glyphs, false)
+    }
+}
+
+/// Returns how many rows the column labels above the board take
+pub fn label_rows(board: &Board) -> usize {
+    coordinates::column_name(board.width().saturating_sub(1)).len()
+}
+
+/// Returns the intersection a renderer draws at a column and row of its text, if any.
+///
+/// After the margin, every intersection takes two columns, its own and the line to the next one;
+/// The lines, the margin and the labels aren't part of any intersection.
+pub fn intersection_at(board: &Board, column: usize, row: usize) -> Option<(usize, usize)> {
+    if column.is_multiple_of(2) {
+        return None;
+    }
+
+    let x = column / 2;
+    let y = row.checked_sub(label_rows(board))?;
+
+    if x < board.width() && y < board.height() {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// The characters a renderer draws a board with
+struct Glyphs {
+    black: char,
+    white: char,
+    marked_black: char,
+    marked_white: char,
+    ghost: char,
+    /// Drawn on every empty intersection; Without one, the box drawing corners, edges and crossings are
+    empty: Option<char>,
+    /// Drawn between intersections
+    line: char,
+    /// Drawn before and after the cursor; Without them, the cursor is drawn in reverse video
+    cursor: Option<(char, char)>,
+}
+
+/// Returns the box drawing character for an empty intersection, depending on the edges it is on
+fn box_symbol(board: &Board, x: usize, y: usize) -> char {
+    let top = y == 0;
+    let bottom = y == board.height() - 1;
+    let left = x == 0;
+    let right = x == board.width() - 1;
+
+    match (top, bottom, left, right) {
+        (true, _, true, _) => BOX_TL_CORNER,
+        (true, _, _, true) => BOX_TR_CORNER,
+        (true, _, _, _) => BOX_TOP_EDGE,
+        (_, true, true, _) => BOX_BL_CORNER,
+        (_, true, _, true) => BOX_BR_CORNER,
+        (_, true, _, _) => BOX_BOTTOM_EDGE,
+        (_, _, true, _) => BOX_LEFT_EDGE,
+        (_, _, _, true) => BOX_RIGHT_EDGE,
+        _ => BOX_INTERSECTION,
+    }
+}
+
+/// Adds an escape code, like a colour, to the text
+fn escape(out: &mut String, code: &str) {
+    out.push_str(ANSI_BASE);
+    out.push_str(code);
+}
+
+/// Draws a board in the layout all renderers share
+fn draw(board: &Board, overlay: &Overlay, glyphs: &Glyphs, colored: bool) -> String {
     let data = board.get_data();
+    let mut out = String::new();
+
+    if colored {
+        escape(&mut out, &format!("{}{}", ANSI_SET_BG, BG_COLOR));
+        escape(&mut out, &format!("{}{}", ANSI_SET_FG, LINE_COLOR));
+    }
 
-    print!("{}{}{}", ANSI_BASE, ANSI_SET_BG, BG_COLOR);
-    print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, LINE_COLOR);
+    // Wide boards have longer column names, which are written top to bottom over several rows
+    let names = (0..board.width())
+        .map(coordinates::column_name)
+        .collect::<Vec<String>>();
+    let label_rows = label_rows(board);
 
-    for x in 0..board.size() {
-        if x > board.size() {
-            panic!("Max board size is 26x26");
-        }
+    for label_row in 0..label_rows {
+        // Leave the margin free
+        out.push(' ');
 
-        let num_string = crate::ASCII.chars().nth(x).unwrap();
+        for name in &names {
+            // Right aligned, so the last letter is always in the bottom row
+            let letter = (label_row + name.len())
+                .checked_sub(label_rows)
+                .and_then(|i| name.chars().nth(i))
+                .unwrap_or(' ');
 
-        print!("{} ", num_string);
+            out.push(letter);
+            out.push(' ');
+        }
+        // Print a space in the end to fill the board top right corner
+        out.push(' ');
+
+        out.push('\n');
     }
-    // Print a space in the end to fill the board top right corner
-    print!(" ");
 
-    print!("\n");
+    for (y, row) in data.iter().enumerate() {
+        let mut y_indicator_string = coordinates::row_name(y, board.height());
+
+        while y_indicator_string.len() < 2 {
+            y_indicator_string.push(' ');
+        }
 
-    for y in 0..board.size() {
-        for x in 0..board.size() {
-            let state = data[y][x];
+        // Draws what comes after the intersection at left, or the margin for None,
+        // with brackets around the cursor if the renderer uses them
+        let separator = |left: Option<usize>, plain: char| {
+            let right = left.map_or(0, |x| x + 1);
 
-            let mut y_indicator_string = (y + 1).to_string();
+            match glyphs.cursor {
+                Some((_, close)) if left.is_some() && overlay.cursor == left.map(|x| (x, y)) => {
+                    close
+                }
+                Some((open, _)) if overlay.cursor == Some((right, y)) => open,
+                _ => plain,
+            }
+        };
+
+        if colored {
+            escape(&mut out, &format!("{}{}", ANSI_SET_BG, BG_COLOR));
+        }
+        out.push(separator(None, ' '));
 
-            while y_indicator_string.len() < 2 {
-                y_indicator_string.push(' ');
+        for (x, &state) in row.iter().enumerate() {
+            if colored {
+                escape(&mut out, &format!("{}{}", ANSI_SET_BG, BG_COLOR));
             }
 
-            print!("{}{}{}", ANSI_BASE, ANSI_SET_BG, BG_COLOR);
-
-            if state == IntersectionState::Empty {
-                print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, LINE_COLOR);
-                if y == 0 {
-                    if x == 0 {
-                        print!("{}", BOX_TL_CORNER);
-                    } else if x == board.size() - 1 {
-                        print!("{} {}", BOX_TR_CORNER, y_indicator_string);
-                    } else {
-                        print!("{}", BOX_TOP_EDGE);
-                    }
-                } else if y == board.size() - 1 {
-                    if x == 0 {
-                        print!("{}", BOX_BL_CORNER);
-                    } else if x == board.size() - 1 {
-                        print!("{} {}", BOX_BR_CORNER, y_indicator_string);
-                    } else {
-                        print!("{}", BOX_BOTTOM_EDGE);
-                    }
-                } else {
-                    if x == 0 {
-                        print!("{}", BOX_LEFT_EDGE);
-                    } else if x == board.size() - 1 {
-                        print!("{} {}", BOX_RIGHT_EDGE, y_indicator_string);
-                    } else {
-                        print!("{}", BOX_INTERSECTION);
-                    }
+            let highlighted = glyphs.cursor.is_none() && overlay.cursor == Some((x, y));
+            if highlighted {
+                escape(&mut out, ANSI_REVERSE);
+            }
+
+            let ghost = match overlay.ghost {
+                Some((ghost_x, ghost_y, ghost_state)) if (ghost_x, ghost_y) == (x, y) => {
+                    Some(ghost_state)
                 }
-            } else {
-                match state {
-                    IntersectionState::Black => {
-                        print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, BLACK_COLOR);
-                    }
-                    IntersectionState::White => {
-                        print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, WHITE_COLOR);
-                    }
-                    IntersectionState::Empty => unreachable!(),
+                _ => None,
+            };
+
+            let stone_color = |stone| match stone {
+                IntersectionState::White => WHITE_COLOR,
+                _ => BLACK_COLOR,
+            };
+
+            let (color, symbol, faint) = match (state, ghost) {
+                (IntersectionState::Empty, Some(ghost_state)) => {
+                    (stone_color(ghost_state), glyphs.ghost, true)
+                }
+                (IntersectionState::Empty, None) => {
+                    let symbol = glyphs.empty.unwrap_or_else(|| box_symbol(board, x, y));
+                    (LINE_COLOR, symbol, false)
                 }
-                print!("{}", WHITE_STONE);
-                if x == board.size() - 1 {
-                    print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, LINE_COLOR);
-                    print!(" {}", y_indicator_string);
+                (stone, _) => {
+                    let marked = overlay.marked.contains(&(x, y));
+                    let symbol = match (stone, marked) {
+                        (IntersectionState::White, true) => glyphs.marked_white,
+                        (IntersectionState::White, false) => glyphs.white,
+                        (_, true) => glyphs.marked_black,
+                        (_, false) => glyphs.black,
+                    };
+                    (stone_color(stone), symbol, false)
+                }
+            };
+
+            if colored {
+                escape(&mut out, &format!("{}{}", ANSI_SET_FG, color));
+                if faint {
+                    escape(&mut out, ANSI_FAINT);
                 }
             }
+            out.push(symbol);
+            if colored && faint {
+                escape(&mut out, ANSI_NO_FAINT);
+            }
 
-            if x != board.size() - 1 {
-                print!("{}{}{}", ANSI_BASE, ANSI_SET_FG, LINE_COLOR);
-                print!("{}", BOX_LINE);
+            if highlighted {
+                escape(&mut out, ANSI_NO_REVERSE);
+            }
+
+            if colored {
+                escape(&mut out, &format!("{}{}", ANSI_SET_FG, LINE_COLOR));
+            }
+            if x != board.width() - 1 {
+                out.push(separator(Some(x), glyphs.line));
+            } else {
+                out.push(separator(Some(x), ' '));
+                out.push_str(&y_indicator_string);
             }
         }
-        print!("{}{}", ANSI_BASE, ANSI_RESET);
-        print!("\n");
+        if colored {
+            escape(&mut out, ANSI_RESET);
+        }
+        out.push('\n');
+    }
+    if colored {
+        escape(&mut out, ANSI_RESET);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnsiRenderer, AsciiRenderer, Overlay, Renderer, UnicodeRenderer};
+    use crate::board::{Board, IntersectionState::*};
+    use std::collections::HashSet;
+
+    /// A 3x3 board with a stone of each colour, a dead white stone, the cursor and a black ghost stone
+    fn example() -> (Board, Overlay) {
+        let mut board = Board::new(3);
+        board.set_intersection(0, 0, Black).unwrap();
+        board.set_intersection(1, 1, White).unwrap();
+        board.set_intersection(2, 2, White).unwrap();
+
+        let overlay = Overlay {
+            marked: HashSet::from([(2, 2)]),
+            cursor: Some((2, 0)),
+            ghost: Some((0, 2, Black)),
+        };
+
+        (board, overlay)
+    }
+
+    #[test]
+    pub fn ascii_snapshot() {
+        let (board, overlay) = example();
+
+        let expected = concat!(" A B C  \n", " X .[.]3 \n", " . O . 2 \n", " * . o 1 \n");
+        assert_eq!(AsciiRenderer.render(&board, &overlay), expected);
+
+        // On the left edge, the opening bracket takes the place of the margin
+        let overlay = Overlay {
+            cursor: Some((0, 1)),
+            ..overlay
+        };
+        let drawn = AsciiRenderer.render(&board, &overlay);
+        assert_eq!(drawn.lines().nth(2), Some("[.]O . 2 "));
+    }
+
+    #[test]
+    pub fn unicode_snapshot() {
+        let (board, overlay) = example();
+
+        let expected = concat!(" A B C  \n", " ○─┬[┐]3 \n", " ├─●─┤ 2 \n", " ◌─┴─× 1 \n");
+        assert_eq!(UnicodeRenderer.render(&board, &overlay), expected);
+    }
+
+    #[test]
+    pub fn ansi_snapshot() {
+        let (board, overlay) = example();
+
+        // The wooden background and the colours of the lines and stones, with the ghost stone dimmed
+        let expected = concat!(
+            "\x1b[42;2;194;171;43m\x1b[38;2;194;148;30m A B C  \n",
+            "\x1b[42;2;194;171;43m \x1b[42;2;194;171;43m\x1b[38;2;0;0;34m●\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[38;2;194;148;30m┬\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[7m\x1b[38;2;194;148;30m┐\x1b[27m\x1b[38;2;194;148;30m 3 \x1b[0m\n",
+            "\x1b[42;2;194;171;43m \x1b[42;2;194;171;43m\x1b[38;2;194;148;30m├\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[38;2;255;255;255m●\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[38;2;194;148;30m┤\x1b[38;2;194;148;30m 2 \x1b[0m\n",
+            "\x1b[42;2;194;171;43m \x1b[42;2;194;171;43m\x1b[38;2;0;0;34m\x1b[2m●\x1b[22m\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[38;2;194;148;30m┴\x1b[38;2;194;148;30m─\x1b[42;2;194;171;43m\x1b[38;2;255;255;255m×\x1b[38;2;194;148;30m 1 \x1b[0m\n",
+            "\x1b[0m"
+        );
+        assert_eq!(AnsiRenderer.render(&board, &overlay), expected);
+    }
+
+    #[test]
+    pub fn write_board() {
+        let (board, overlay) = example();
+        let mut out = Vec::new();
+
+        AsciiRenderer
+            .write_board(&mut out, &board, &overlay)
+            .unwrap();
+        assert_eq!(out, AsciiRenderer.render(&board, &overlay).into_bytes());
+
+        let names = super::RENDERER_NAMES;
+        assert!(names
+            .iter()
+            .all(|name| super::renderer_from_name(name).is_some()));
+        assert!(super::renderer_from_name("braille").is_none());
+    }
+
+    #[test]
+    pub fn intersection_at() {
+        // 30 columns have two letter names, so the labels take two rows
+        let board = Board::new_rect(30, 5);
+
+        assert_eq!(super::intersection_at(&board, 1, 2), Some((0, 0)));
+        assert_eq!(super::intersection_at(&board, 5, 3), Some((2, 1)));
+        assert_eq!(super::intersection_at(&board, 59, 6), Some((29, 4)));
+
+        // Every cell of the drawing either is an intersection or none at all
+        let drawn = AsciiRenderer.render(&board, &Overlay::default());
+        for (row, line) in drawn.lines().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                let intersection = super::intersection_at(&board, column, row);
+                let expected = (character == '.').then(|| ((column - 1) / 2, row - 2));
+                assert_eq!(
+                    intersection, expected,
+                    "{:?} at {}, {}",
+                    character, column, row
+                );
+            }
+        }
+
+        // The label rows
+        assert_eq!(super::intersection_at(&board, 1, 0), None);
+        assert_eq!(super::intersection_at(&board, 1, 1), None);
+        // The margin and the lines between intersections
+        assert_eq!(super::intersection_at(&board, 0, 2), None);
+        assert_eq!(super::intersection_at(&board, 2, 2), None);
+        assert_eq!(super::intersection_at(&board, 58, 6), None);
+        // The row labels on the right
+        assert_eq!(super::intersection_at(&board, 61, 6), None);
+        assert_eq!(super::intersection_at(&board, 62, 6), None);
+        // Below the board, and far outside it, as terminals can report
+        assert_eq!(super::intersection_at(&board, 1, 7), None);
+        assert_eq!(super::intersection_at(&board, usize::MAX, usize::MAX), None);
+        assert_eq!(super::intersection_at(&board, u16::MAX as usize, 3), None);
+
+        // A board without intersections has none to click
+        let empty = Board::new_rect(0, 0);
+        assert_eq!(super::intersection_at(&empty, 1, 0), None);
+        assert_eq!(super::intersection_at(&empty, 1, 1), None);
     }
-    print!("{}{}", ANSI_BASE, ANSI_RESET);
 }
@@ -0,0 +1,142 @@
+/// Describes how repeated board positions are handled
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KoRule {
+    /// Positions may repeat freely
+    None,
+    /// A move may not recreate the position from before the previous move
+    #[default]
+    Simple,
+    /// Positional superko (PSK);
+    /// A move may not recreate any earlier position
+    PositionalSuperko,
+    /// Situational superko (SSK);
+    /// A move may not recreate any earlier position with the same player to move
+    SituationalSuperko,
+}
+
+impl KoRule {
+    /// Tries to parse a ko rule from its name or abbreviation
+    pub fn from_name(name: &str) -> Option<KoRule> {
+        match name.to_lowercase().as_str() {
+            "none" | "off" => Some(Self::None),
+            "simple" | "ko" => Some(Self::Simple),
+            "psk" | "positional" => Some(Self::PositionalSuperko),
+            "ssk" | "situational" => Some(Self::SituationalSuperko),
+            _ => None,
+        }
+    }
+}
+
+/// Describes whether a player may capture their own stones
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SuicideRule {
+    /// A move which leaves its own group without liberties is illegal
+    #[default]
+    Forbidden,
+    /// A move may capture its own group, as long as it is more than the single stone played
+    Allowed,
+}
+
+/// Describes how a finished game is scored
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ScoringRule {
+    /// Stones on the board plus surrounded territory
+    #[default]
+    Area,
+    /// Surrounded territory plus captured and dead stones
+    Territory,
+}
+
+/// Defines the rules a game is played under.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ruleset {
+    pub ko_rule: KoRule,
+    pub suicide_rule: SuicideRule,
+    pub scoring_rule: ScoringRule,
+    /// Compensation points given to white for moving second
+    pub komi: f32,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self::japanese()
+    }
+}
+
+impl Ruleset {
+    /// Japanese rules; simple ko, no suicide, territory scoring
+    pub fn japanese() -> Ruleset {
+        Ruleset {
+            ko_rule: KoRule::Simple,
+            suicide_rule: SuicideRule::Forbidden,
+            scoring_rule: ScoringRule::Territory,
+            komi: 6.5,
+        }
+    }
+
+    /// Chinese rules; positional superko, no suicide, area scoring
+    pub fn chinese() -> Ruleset {
+        Ruleset {
+            ko_rule: KoRule::PositionalSuperko,
+            suicide_rule: SuicideRule::Forbidden,
+            scoring_rule: ScoringRule::Area,
+            komi: 7.5,
+        }
+    }
+
+    /// AGA rules; situational superko, no suicide, area scoring
+    pub fn aga() -> Ruleset {
+        Ruleset {
+            ko_rule: KoRule::SituationalSuperko,
+            suicide_rule: SuicideRule::Forbidden,
+            scoring_rule: ScoringRule::Area,
+            komi: 7.5,
+        }
+    }
+
+    /// New Zealand rules; situational superko, suicide allowed, area scoring
+    pub fn new_zealand() -> Ruleset {
+        Ruleset {
+            ko_rule: KoRule::SituationalSuperko,
+            suicide_rule: SuicideRule::Allowed,
+            scoring_rule: ScoringRule::Area,
+            komi: 7.0,
+        }
+    }
+
+    /// Tromp-Taylor rules; positional superko, suicide allowed, area scoring
+    pub fn tromp_taylor() -> Ruleset {
+        Ruleset {
+            ko_rule: KoRule::PositionalSuperko,
+            suicide_rule: SuicideRule::Allowed,
+            scoring_rule: ScoringRule::Area,
+            komi: 7.5,
+        }
+    }
+
+    /// Returns the name of the preset this ruleset matches, if any
+    pub fn name(&self) -> Option<&'static str> {
+        [
+            (Self::japanese(), "Japanese"),
+            (Self::chinese(), "Chinese"),
+            (Self::aga(), "AGA"),
+            (Self::new_zealand(), "NZ"),
+            (Self::tromp_taylor(), "Tromp-Taylor"),
+        ]
+        .into_iter()
+        .find(|(preset, _)| preset == self)
+        .map(|(_, name)| name)
+    }
+
+    /// Tries to find a ruleset by its name
+    pub fn from_name(name: &str) -> Option<Ruleset> {
+        match name.to_lowercase().as_str() {
+            "japanese" | "jp" => Some(Self::japanese()),
+            "chinese" | "cn" => Some(Self::chinese()),
+            "aga" => Some(Self::aga()),
+            "nz" | "new-zealand" | "newzealand" => Some(Self::new_zealand()),
+            "tt" | "tromp-taylor" | "tromptaylor" => Some(Self::tromp_taylor()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use crate::board::{Board, IntersectionState};
+use crate::gamestate::BoardState;
+use crate::ruleset::{Ruleset, ScoringRule};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A region of connected empty intersections
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EmptyRegion {
+    pub intersections: HashSet<(usize, usize)>,
+    /// The states of the stones surrounding the region
+    pub borders: HashSet<IntersectionState>,
+}
+
+impl EmptyRegion {
+    /// Returns the player owning the region, if it is surrounded by only their stones
+    pub fn owner(&self) -> Option<IntersectionState> {
+        if self.borders.len() != 1 {
+            return None;
+        }
+
+        self.borders.iter().next().copied()
+    }
+}
+
+/// The points scored by a single player
+///
+/// Fields which don't count under the used scoring rule are left at 0.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PlayerScore {
+    /// The number of the player's stones on the board (area scoring)
+    pub stones: usize,
+    /// The number of empty intersections surrounded by the player
+    pub territory: usize,
+    /// The number of stones the player captured during the game (territory scoring)
+    pub prisoners: usize,
+    /// The number of opponent stones removed as dead at the end of the game (territory scoring)
+    pub dead_stones: usize,
+    /// Compensation points, only given to white
+    pub komi: f32,
+}
+
+impl PlayerScore {
+    /// Returns the total score of the player
+    pub fn total(&self) -> f32 {
+        (self.stones + self.territory + self.prisoners + self.dead_stones) as f32 + self.komi
+    }
+}
+
+/// Describes the score of a game
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct ScoreReport {
+    /// The rule the game was scored with
+    pub method: ScoringRule,
+    pub black: PlayerScore,
+    pub white: PlayerScore,
+}
+
+impl ScoreReport {
+    /// Returns the player with the higher score, or None for a draw (jigo)
+    pub fn winner(&self) -> Option<IntersectionState> {
+        let black = self.black.total();
+        let white = self.white.total();
+
+        if black > white {
+            Some(IntersectionState::Black)
+        } else if white > black {
+            Some(IntersectionState::White)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the difference between the two scores
+    pub fn margin(&self) -> f32 {
+        (self.black.total() - self.white.total()).abs()
+    }
+}
+
+impl fmt::Display for ScoreReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.winner() {
+            Some(winner) => write!(f, "{} wins by {}", winner.name(), self.margin()),
+            None => write!(f, "Draw"),
+        }
+    }
+}
+
+/// Finds all regions of connected empty intersections on a board
+pub fn find_empty_regions(board: &Board) -> Vec<EmptyRegion> {
+    let mut regions = Vec::new();
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if visited.contains(&(x, y))
+                || board.get_intersection(x, y) != Some(IntersectionState::Empty)
+            {
+                continue;
+            }
+
+            // A group of empty intersections is exactly a region
+            let intersections = board.find_intersections_in_group(x, y).unwrap();
+
+            let mut borders = HashSet::new();
+
+            for intersection in intersections.iter() {
+                for neighbour in board
+                    .intersection_neighbours(intersection.0, intersection.1)
+                    .unwrap()
+                {
+                    let state = board.get_intersection(neighbour.0, neighbour.1).unwrap();
+
+                    if state != IntersectionState::Empty {
+                        borders.insert(state);
+                    }
+                }
+            }
+
+            visited.extend(intersections.iter());
+            regions.push(EmptyRegion {
+                intersections,
+                borders,
+            });
+        }
+    }
+
+    regions
+}
+
+/// Returns a copy of the board without the dead stones
+fn removed_dead_stones(board: &Board, dead_stones: &HashSet<(usize, usize)>) -> Board {
+    let mut board = board.clone();
+
+    for stone in dead_stones {
+        let _ = board.set_intersection(stone.0, stone.1, IntersectionState::Empty);
+    }
+
+    board
+}
+
+/// Finds all stones in seki.
+///
+/// A region bordered by both players is a shared liberty of a seki if the groups around it can't
+/// capture each other: none of them is in atari, and whoever fills a point of the region puts their
+/// own group into atari without capturing anything. Every group next to a shared liberty is in seki.
+///
+/// This assumes neutral points (dame) were filled before the game ended.
+pub fn find_seki_stones(board: &Board) -> HashSet<(usize, usize)> {
+    let mut seki_stones = HashSet::new();
+
+    for region in find_empty_regions(board) {
+        if region.borders.len() < 2 {
+            continue;
+        }
+
+        let mut groups: Vec<HashSet<(usize, usize)>> = Vec::new();
+        let mut in_atari = false;
+
+        for intersection in region.intersections.iter() {
+            for neighbour in board
+                .intersection_neighbours(intersection.0, intersection.1)
+                .unwrap()
+            {
+                if board.get_intersection(neighbour.0, neighbour.1)
+                    == Some(IntersectionState::Empty)
+                    || groups.iter().any(|group| group.contains(&neighbour))
+                {
+                    continue;
+                }
+
+                if board
+                    .find_true_liberties(neighbour.0, neighbour.1)
+                    .unwrap()
+                    .len()
+                    < 2
+                {
+                    in_atari = true;
+                }
+
+                groups.push(
+                    board
+                        .find_intersections_in_group(neighbour.0, neighbour.1)
+                        .unwrap(),
+                );
+            }
+        }
+
+        // A group in atari can simply be captured
+        if in_atari {
+            continue;
+        }
+
+        let players = [IntersectionState::Black, IntersectionState::White];
+        let is_seki = region.intersections.iter().all(|&(x, y)| {
+            players.iter().all(|&player| {
+                let mut filled = board.clone();
+
+                match filled.play_stone(x, y, player) {
+                    Some(0) => filled.find_true_liberties(x, y).unwrap().len() < 2,
+                    Some(_) => false,
+                    // Suicide, which is no way to capture either
+                    None => true,
+                }
+            })
+        });
+
+        if is_seki {
+            for group in groups {
+                seki_stones.extend(group);
+            }
+        }
+    }
+
+    seki_stones
+}
+
+/// Scores a board with area scoring (Chinese / Tromp-Taylor)
+///
+/// Each player gets a point for every one of their stones on the board,
+/// and for every empty intersection only their stones surround.
+///
+/// Dead stones are removed before counting.
+pub fn area_score(board: &Board, dead_stones: &HashSet<(usize, usize)>, komi: f32) -> ScoreReport {
+    let board = removed_dead_stones(board, dead_stones);
+
+    let mut report = ScoreReport {
+        method: ScoringRule::Area,
+        ..Default::default()
+    };
+    report.white.komi = komi;
+
+    for row in board.get_data() {
+        for state in row {
+            match state {
+                IntersectionState::Black => report.black.stones += 1,
+                IntersectionState::White => report.white.stones += 1,
+                IntersectionState::Empty => {}
+            }
+        }
+    }
+
+    for region in find_empty_regions(&board) {
+        match region.owner() {
+            Some(IntersectionState::Black) => report.black.territory += region.intersections.len(),
+            Some(IntersectionState::White) => report.white.territory += region.intersections.len(),
+            _ => {}
+        }
+    }
+
+    report
+}
+
+/// Scores a boardstate with territory scoring (Japanese / Korean)
+///
+/// Each player gets a point for every empty intersection only their stones surround,
+/// for every stone they captured and for every dead opponent stone.
+///
+/// Territory next to groups in seki doesn't count.
+pub fn territory_score(
+    boardstate: &BoardState,
+    dead_stones: &HashSet<(usize, usize)>,
+    komi: f32,
+) -> ScoreReport {
+    let board = removed_dead_stones(&boardstate.board, dead_stones);
+
+    let mut report = ScoreReport {
+        method: ScoringRule::Territory,
+        ..Default::default()
+    };
+    report.white.komi = komi;
+    report.black.prisoners = boardstate.black_prisoners;
+    report.white.prisoners = boardstate.white_prisoners;
+
+    for stone in dead_stones {
+        match boardstate.board.get_intersection(stone.0, stone.1) {
+            Some(IntersectionState::Black) => report.white.dead_stones += 1,
+            Some(IntersectionState::White) => report.black.dead_stones += 1,
+            _ => {}
+        }
+    }
+
+    let seki_stones = find_seki_stones(&board);
+
+    for region in find_empty_regions(&board) {
+        let owner = region.owner();
+
+        if owner.is_none() {
+            continue;
+        }
+
+        let next_to_seki = region.intersections.iter().any(|intersection| {
+            board
+                .intersection_neighbours(intersection.0, intersection.1)
+                .unwrap()
+                .iter()
+                .any(|neighbour| seki_stones.contains(neighbour))
+        });
+
+        if next_to_seki {
+            continue;
+        }
+
+        match owner {
+            Some(IntersectionState::Black) => report.black.territory += region.intersections.len(),
+            Some(IntersectionState::White) => report.white.territory += region.intersections.len(),
+            _ => {}
+        }
+    }
+
+    report
+}
+
+/// Scores a boardstate with the scoring rule and komi of the ruleset
+pub fn score(
+    boardstate: &BoardState,
+    dead_stones: &HashSet<(usize, usize)>,
+    ruleset: &Ruleset,
+) -> ScoreReport {
+    match ruleset.scoring_rule {
+        ScoringRule::Area => area_score(&boardstate.board, dead_stones, ruleset.komi),
+        ScoringRule::Territory => territory_score(boardstate, dead_stones, ruleset.komi),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, IntersectionState};
+    use crate::gamestate::BoardState;
+    use std::collections::HashSet;
+
+    /// Builds a board from rows of text, with X for black and O for white stones
+    fn board_from_rows(rows: &[&str]) -> Board {
+        let mut board = Board::new(rows.len());
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().filter(|c| *c != ' ').enumerate() {
+                let state = match character {
+                    'X' => IntersectionState::Black,
+                    'O' => IntersectionState::White,
+                    _ => IntersectionState::Empty,
+                };
+                board.set_intersection(x, y, state).unwrap();
+            }
+        }
+
+        board
+    }
+
+    /// Builds a board where black owns columns 0 - 3 and white owns columns 5 - 8
+    fn split_board() -> Board {
+        board_from_rows(&[
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+            ". . . X . O . . .",
+        ])
+    }
+
+    #[test]
+    pub fn empty_board() {
+        let report = super::area_score(&Board::new(9), &HashSet::new(), 7.5);
+
+        assert_eq!(report.black.total(), 0.0);
+        assert_eq!(report.white.total(), 7.5);
+        assert_eq!(report.winner(), Some(IntersectionState::White));
+    }
+
+    #[test]
+    pub fn area_split_board() {
+        let board = split_board();
+
+        let regions = super::find_empty_regions(&board);
+        assert_eq!(regions.len(), 3);
+
+        let report = super::area_score(&board, &HashSet::new(), 0.5);
+
+        assert_eq!(report.black.stones, 9);
+        assert_eq!(report.black.territory, 27);
+        assert_eq!(report.white.stones, 9);
+        assert_eq!(report.white.territory, 27);
+
+        assert_eq!(report.winner(), Some(IntersectionState::White));
+        assert_eq!(report.margin(), 0.5);
+
+        let report = super::area_score(&board, &HashSet::new(), 0.0);
+        assert_eq!(report.winner(), None);
+    }
+
+    #[test]
+    pub fn territory_split_board() {
+        let mut boardstate = BoardState {
+            board: split_board(),
+            black_prisoners: 2,
+            white_prisoners: 1,
+            ..Default::default()
+        };
+
+        // A dead white stone inside black's territory
+        boardstate
+            .board
+            .set_intersection(0, 0, IntersectionState::White)
+            .unwrap();
+
+        let dead_stones = HashSet::from([(0, 0)]);
+
+        let report = super::territory_score(&boardstate, &dead_stones, 6.5);
+
+        assert_eq!(report.black.stones, 0);
+        assert_eq!(report.black.territory, 27);
+        assert_eq!(report.black.prisoners, 2);
+        assert_eq!(report.black.dead_stones, 1);
+        assert_eq!(report.black.total(), 30.0);
+        assert_eq!(report.white.territory, 27);
+        assert_eq!(report.white.total(), 34.5);
+
+        // Area scoring on the same board, where only stones on the board count
+        let report = super::area_score(&boardstate.board, &dead_stones, 6.5);
+
+        assert_eq!(report.black.total(), 36.0);
+        assert_eq!(report.white.total(), 42.5);
+    }
+
+    #[test]
+    pub fn seki() {
+        // Black and white share a liberty at (2, 0), and each have a single eye
+        let boardstate = BoardState {
+            board: board_from_rows(&[
+                ". X . O .",
+                "X X X O O",
+                "O O O O O",
+                "O O O O O",
+                "O O O O O",
+            ]),
+            ..Default::default()
+        };
+
+        let seki_stones = super::find_seki_stones(&boardstate.board);
+        assert_eq!(seki_stones.len(), 22);
+
+        // No territory inside seki
+        let report = super::territory_score(&boardstate, &HashSet::new(), 0.0);
+        assert_eq!(report.black.territory, 0);
+        assert_eq!(report.white.territory, 0);
+
+        // Area scoring still counts the eyes
+        let report = super::area_score(&boardstate.board, &HashSet::new(), 0.0);
+        assert_eq!(report.black.total(), 5.0);
+        assert_eq!(report.white.total(), 19.0);
+
+        // Neutral points between living groups aren't seki
+        assert!(super::find_seki_stones(&split_board()).is_empty());
+    }
+
+    #[test]
+    pub fn atari_is_not_seki() {
+        // The white stone in the corner is in atari at (0, 1), where black can capture it
+        let boardstate = BoardState {
+            board: board_from_rows(&[
+                "O X . . .",
+                ". X . . .",
+                "X X X X X",
+                "O O O O O",
+                ". . . . .",
+            ]),
+            ..Default::default()
+        };
+
+        assert!(super::find_seki_stones(&boardstate.board).is_empty());
+
+        // So the territory of the black group next to it still counts
+        let report = super::territory_score(&boardstate, &HashSet::new(), 0.0);
+        assert_eq!(report.black.territory, 6);
+        assert_eq!(report.white.territory, 5);
+    }
+}
@@ -0,0 +1,884 @@
+use crate::board::{Board, IntersectionState};
+use crate::coordinates::{from_sgf_point, to_sgf_point};
+use crate::errors::GoError;
+use crate::gamecommand::GameCommand;
+use crate::gamestate::{GameState, GameStatus};
+use crate::gametree::GameTree;
+use crate::ruleset::Ruleset;
+
+/// The name gorst writes into the AP (application) property
+pub const APPLICATION_NAME: &str = "gorst";
+
+/// The widest and tallest board SGF points can name, with a - z and A - Z
+const MAX_SIZE: usize = 52;
+
+/// to_sgf refuses larger boards before writing any point
+const SIZE_CHECKED: &str = "to_sgf only writes boards up to 52x52";
+
+/// Escapes the characters SGF text values can't contain
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+/// Returns the letter SGF uses for a player
+fn player_letter(player: IntersectionState) -> &'static str {
+    match player {
+        IntersectionState::White => "W",
+        _ => "B",
+    }
+}
+
+/// Returns the SGF setup properties (AB, AW, AE) which turn one board into another
+fn setup_properties(before: &Board, after: &Board) -> String {
+    let mut black = String::new();
+    let mut white = String::new();
+    let mut empty = String::new();
+
+    for y in 0..after.height() {
+        for x in 0..after.width() {
+            let state = after.get_intersection(x, y);
+
+            if before.get_intersection(x, y) == state {
+                continue;
+            }
+
+            let point = format!("[{}]", to_sgf_point(x, y).expect(SIZE_CHECKED));
+
+            match state {
+                Some(IntersectionState::Black) => black.push_str(&point),
+                Some(IntersectionState::White) => white.push_str(&point),
+                _ => empty.push_str(&point),
+            }
+        }
+    }
+
+    let mut properties = String::new();
+
+    for (name, values) in [("AB", black), ("AW", white), ("AE", empty)] {
+        if !values.is_empty() {
+            properties.push_str(name);
+            properties.push_str(&values);
+        }
+    }
+
+    properties
+}
+
+/// Returns whether a node of the game tree was reached by a setup command
+fn is_setup(tree: &GameTree, index: usize) -> bool {
+    matches!(
+        tree.node(index).unwrap().boardstate.last_move,
+        Some(GameCommand::Setup(..))
+    )
+}
+
+/// Returns the last node of the setup commands which follow the given node without branching
+fn setup_run_end(tree: &GameTree, index: usize) -> usize {
+    let mut node = index;
+
+    while let [child] = tree.node(node).unwrap().children() {
+        if !is_setup(tree, *child) {
+            break;
+        }
+        node = *child;
+    }
+
+    node
+}
+
+/// Returns the comment property for the nodes from one node down to another, if they have comments
+fn comment_property(tree: &GameTree, from: usize, to: usize) -> String {
+    let path = tree.path_to(to);
+    let start = path.iter().position(|&node| node == from).unwrap_or(0);
+
+    let comments: Vec<&str> = path[start..]
+        .iter()
+        .map(|&node| tree.node(node).unwrap().comment.as_str())
+        .filter(|comment| !comment.is_empty())
+        .collect();
+
+    if comments.is_empty() {
+        return String::new();
+    }
+
+    format!("C[{}]", escape_text(&comments.join("\n")))
+}
+
+/// Writes a game as an SGF (FF[4]) game record, with all of its variations.
+///
+/// Setup stones placed before the first move go into the root node, later ones get their own node.
+/// Boards wider or taller than 52 can't be written.
+pub fn to_sgf(gamestate: &GameState) -> Result<String, GoError> {
+    let tree = gamestate.tree();
+    let board = &tree.node(GameTree::ROOT).unwrap().boardstate.board;
+
+    if board.width() > MAX_SIZE || board.height() > MAX_SIZE {
+        return Err(GoError::SgfBoardTooLarge {
+            width: board.width(),
+            height: board.height(),
+        });
+    }
+
+    // Square boards are written with a single number, as the specification requires
+    let size = if board.is_square() {
+        board.width().to_string()
+    } else {
+        format!("{}:{}", board.width(), board.height())
+    };
+
+    let mut sgf = format!(
+        "(;GM[1]FF[4]CA[UTF-8]AP[{}]SZ[{}]KM[{}]",
+        APPLICATION_NAME, size, gamestate.ruleset.komi
+    );
+
+    if let Some(name) = gamestate.ruleset.name() {
+        sgf.push_str(&format!("RU[{}]", name));
+    }
+
+    sgf.push_str(&format!(
+        "PB[{}]PW[{}]",
+        escape_text(&gamestate.info.black_player),
+        escape_text(&gamestate.info.white_player)
+    ));
+
+    // The result is the one at the end of the main line
+    match gamestate.status_at(tree.main_line_end(GameTree::ROOT)) {
+        GameStatus::Resigned(player) => {
+            sgf.push_str(&format!("RE[{}+R]", player_letter(player.opposite())));
+        }
+        GameStatus::Scored(report) => match report.winner() {
+            Some(winner) => sgf.push_str(&format!(
+                "RE[{}+{}]",
+                player_letter(winner),
+                report.margin()
+            )),
+            None => sgf.push_str("RE[0]"),
+        },
+        _ => {}
+    }
+
+    // The root node also holds the stones the game started with
+    let setup_end = setup_run_end(tree, GameTree::ROOT);
+
+    sgf.push_str(&setup_properties(
+        &Board::new_rect(board.width(), board.height()),
+        &tree.node(setup_end).unwrap().boardstate.board,
+    ));
+
+    if gamestate.to_move_at(setup_end) == IntersectionState::White {
+        sgf.push_str("PL[W]");
+    }
+
+    sgf.push_str(&comment_property(tree, GameTree::ROOT, setup_end));
+    sgf.push('\n');
+
+    write_continuation(gamestate, setup_end, &mut sgf);
+
+    sgf.push_str(")\n");
+    Ok(sgf)
+}
+
+/// Writes everything which follows the given node, putting variations in parentheses
+fn write_continuation(gamestate: &GameState, index: usize, sgf: &mut String) {
+    let tree = gamestate.tree();
+    let mut node = index;
+
+    loop {
+        match tree.node(node).unwrap().children() {
+            [] => return,
+            [child] => node = write_node(gamestate, *child, sgf),
+            children => {
+                for &child in children {
+                    let mut variation = String::new();
+
+                    let end = write_node(gamestate, child, &mut variation);
+                    write_continuation(gamestate, end, &mut variation);
+
+                    if !variation.is_empty() {
+                        sgf.push_str(&format!("({})", variation));
+                    }
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Writes the node at the given index as an SGF node, returning the index of the last node written.
+///
+/// Setup nodes are written together with the setup nodes following them.
+fn write_node(gamestate: &GameState, index: usize, sgf: &mut String) -> usize {
+    let tree = gamestate.tree();
+    let node = tree.node(index).unwrap();
+    let parent = node.parent().unwrap_or(GameTree::ROOT);
+
+    let (end, mut properties) = match node.boardstate.last_move {
+        Some(GameCommand::Setup(..)) => {
+            let end = setup_run_end(tree, index);
+            let properties = setup_properties(
+                &tree.node(parent).unwrap().boardstate.board,
+                &tree.node(end).unwrap().boardstate.board,
+            );
+            (end, properties)
+        }
+        Some(GameCommand::Set(x, y, state)) => (
+            index,
+            format!(
+                "{}[{}]",
+                player_letter(state),
+                to_sgf_point(x, y).expect(SIZE_CHECKED)
+            ),
+        ),
+        Some(GameCommand::Pass) => (
+            index,
+            format!("{}[]", player_letter(gamestate.to_move_at(parent))),
+        ),
+        // Resigning and resuming aren't moves in SGF
+        _ => (index, String::new()),
+    };
+
+    properties.push_str(&comment_property(tree, index, end));
+
+    if !properties.is_empty() {
+        sgf.push(';');
+        sgf.push_str(&properties);
+    }
+
+    end
+}
+
+/// A property of an SGF node, like B[dd] or AB[aa][bb]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SgfProperty {
+    pub identifier: String,
+    pub values: Vec<String>,
+    /// Where the property starts in the SGF text, counted from 1
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SgfProperty {
+    /// Returns an error about one of this property's values
+    fn invalid_value(&self, value: &str) -> GoError {
+        GoError::SgfInvalidValue {
+            property: self.identifier.clone(),
+            value: value.to_string(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+/// A node of an SGF game tree
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SgfNode {
+    pub properties: Vec<SgfProperty>,
+    /// The nodes which follow this one; the first one continues the main line, the others are variations
+    pub children: Vec<SgfNode>,
+}
+
+impl SgfNode {
+    /// Returns the first property with the given identifier
+    pub fn get_property(&self, identifier: &str) -> Option<&SgfProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.identifier == identifier)
+    }
+
+    /// Returns the first value of the first property with the given identifier
+    fn get_value(&self, identifier: &str) -> Option<(&SgfProperty, &str)> {
+        self.get_property(identifier)
+            .and_then(|property| Some((property, property.values.first()?.as_str())))
+    }
+}
+
+/// Reads SGF text character by character, keeping track of the line and column
+struct SgfParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> SgfParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Result<char, GoError> {
+        let character = self.chars.next().ok_or(GoError::SgfUnexpectedEnd {
+            line: self.line,
+            column: self.column,
+        })?;
+
+        if character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Ok(character)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            let _ = self.next();
+        }
+    }
+
+    /// Returns an error about the next character
+    fn unexpected(&mut self) -> GoError {
+        let (line, column) = (self.line, self.column);
+
+        match self.peek() {
+            Some(character) => GoError::SgfUnexpectedCharacter {
+                character,
+                line,
+                column,
+            },
+            None => GoError::SgfUnexpectedEnd { line, column },
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), GoError> {
+        self.skip_whitespace();
+
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected());
+        }
+
+        self.next().map(|_| ())
+    }
+
+    /// Parses every game tree in the text
+    fn parse_collection(&mut self) -> Result<Vec<SgfNode>, GoError> {
+        let mut game_trees = vec![self.parse_game_tree()?];
+
+        self.skip_whitespace();
+        while self.peek() == Some('(') {
+            game_trees.push(self.parse_game_tree()?);
+            self.skip_whitespace();
+        }
+
+        if self.peek().is_some() {
+            return Err(self.unexpected());
+        }
+
+        Ok(game_trees)
+    }
+
+    /// Parses "(" Sequence GameTree* ")" into the first node of the sequence
+    fn parse_game_tree(&mut self) -> Result<SgfNode, GoError> {
+        self.expect('(')?;
+
+        let mut sequence = vec![];
+
+        self.skip_whitespace();
+        if self.peek() != Some(';') {
+            return Err(self.unexpected());
+        }
+
+        while self.peek() == Some(';') {
+            sequence.push(self.parse_node()?);
+            self.skip_whitespace();
+        }
+
+        let mut variations = vec![];
+
+        while self.peek() == Some('(') {
+            variations.push(self.parse_game_tree()?);
+            self.skip_whitespace();
+        }
+
+        self.expect(')')?;
+
+        // Chain the sequence together, with the variations after the last node
+        let mut node = sequence.pop().unwrap_or_default();
+        node.children = variations;
+
+        while let Some(mut previous) = sequence.pop() {
+            previous.children = vec![node];
+            node = previous;
+        }
+
+        Ok(node)
+    }
+
+    /// Parses ";" Property*
+    fn parse_node(&mut self) -> Result<SgfNode, GoError> {
+        self.expect(';')?;
+
+        let mut node = SgfNode::default();
+
+        self.skip_whitespace();
+        while self.peek().is_some_and(|c| c.is_ascii_uppercase()) {
+            node.properties.push(self.parse_property()?);
+            self.skip_whitespace();
+        }
+
+        Ok(node)
+    }
+
+    /// Parses PropIdent PropValue+
+    fn parse_property(&mut self) -> Result<SgfProperty, GoError> {
+        let (line, column) = (self.line, self.column);
+        let mut identifier = String::new();
+
+        while let Some(character) = self.peek().filter(|c| c.is_ascii_alphabetic()) {
+            self.next()?;
+
+            // FF[3] allowed lowercase letters in identifiers, like AddBlack for AB
+            if character.is_ascii_uppercase() {
+                identifier.push(character);
+            }
+        }
+
+        let mut values = vec![];
+
+        self.skip_whitespace();
+        if self.peek() != Some('[') {
+            return Err(self.unexpected());
+        }
+
+        while self.peek() == Some('[') {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+        }
+
+        Ok(SgfProperty {
+            identifier,
+            values,
+            line,
+            column,
+        })
+    }
+
+    /// Parses "[" text "]", resolving escapes
+    fn parse_value(&mut self) -> Result<String, GoError> {
+        self.expect('[')?;
+
+        let mut value = String::new();
+
+        loop {
+            match self.next()? {
+                ']' => return Ok(value),
+                '\\' => match self.next()? {
+                    // An escaped line break is removed
+                    '\n' => {
+                        if self.peek() == Some('\r') {
+                            self.next()?;
+                        }
+                    }
+                    '\r' => {
+                        if self.peek() == Some('\n') {
+                            self.next()?;
+                        }
+                    }
+                    escaped => value.push(escaped),
+                },
+                character => value.push(character),
+            }
+        }
+    }
+}
+
+/// Parses SGF text into its game trees, without interpreting any properties
+pub fn parse_sgf(text: &str) -> Result<Vec<SgfNode>, GoError> {
+    SgfParser::new(text).parse_collection()
+}
+
+/// Parses the points of a setup property value, which may be a rectangle like "aa:cc"
+fn parse_point_list(
+    property: &SgfProperty,
+    value: &str,
+    width: usize,
+    height: usize,
+) -> Result<Vec<(usize, usize)>, GoError> {
+    let parse_point = |point: &str| {
+        from_sgf_point(point)
+            .filter(|&(x, y)| x < width && y < height)
+            .ok_or_else(|| property.invalid_value(value))
+    };
+
+    let (from, to) = match value.split_once(':') {
+        Some((from, to)) => (parse_point(from)?, parse_point(to)?),
+        None => {
+            let point = parse_point(value)?;
+            (point, point)
+        }
+    };
+
+    let mut points = vec![];
+
+    for y in from.1.min(to.1)..=from.1.max(to.1) {
+        for x in from.0.min(to.0)..=from.0.max(to.0) {
+            points.push((x, y));
+        }
+    }
+
+    Ok(points)
+}
+
+/// Reads the first game of an SGF collection, replaying it and its variations into a GameState.
+///
+/// Every move goes through [GameState::apply_command], so captures are recomputed and illegal
+/// moves are rejected. The returned game is at the end of the main line.
+pub fn from_sgf(text: &str) -> Result<GameState, GoError> {
+    let game_trees = parse_sgf(text)?;
+    let root = &game_trees[0];
+
+    if let Some((property, value)) = root.get_value("GM") {
+        if value.trim() != "1" {
+            return Err(property.invalid_value(value));
+        }
+    }
+
+    // SGF defaults to 19x19; points can only name 52 columns and rows
+    let (mut width, mut height) = (19, 19);
+    if let Some((property, value)) = root.get_value("SZ") {
+        let parse_side = |side: &str| {
+            side.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|side| (1..=MAX_SIZE).contains(side))
+                .ok_or_else(|| property.invalid_value(value))
+        };
+
+        // Either a single size, or the width and height of a rectangular board
+        (width, height) = match value.split_once(':') {
+            Some((w, h)) => (parse_side(w)?, parse_side(h)?),
+            None => {
+                let size = parse_side(value)?;
+                (size, size)
+            }
+        };
+    }
+    let mut gamestate = GameState::with_board(Board::new_rect(width, height));
+
+    // Keep the default rules if we don't know the ones used
+    if let Some(ruleset) = root
+        .get_value("RU")
+        .and_then(|(_, value)| Ruleset::from_name(value.trim()))
+    {
+        gamestate.ruleset = ruleset;
+    }
+
+    if let Some((property, value)) = root.get_value("KM") {
+        gamestate.ruleset.komi = value
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| property.invalid_value(value))?;
+    }
+
+    if let Some((_, value)) = root.get_value("PB") {
+        gamestate.info.black_player = value.to_string();
+    }
+    if let Some((_, value)) = root.get_value("PW") {
+        gamestate.info.white_player = value.to_string();
+    }
+
+    // Each SGF node is replayed from the position its parent lead to
+    let mut nodes = vec![(root, GameTree::ROOT)];
+
+    while let Some((node, from)) = nodes.pop() {
+        gamestate.go_to(from)?;
+        gamestate = replay_node(gamestate, node, width, height)?;
+
+        if let Some((_, comment)) = node.get_value("C") {
+            let existing = &gamestate.tree().current_node().comment;
+
+            let comment = if existing.is_empty() {
+                comment.to_string()
+            } else {
+                format!("{}\n{}", existing, comment)
+            };
+            gamestate.set_comment(comment);
+        }
+
+        // Reversed, so the main line is replayed first and stays the first variation
+        let reached = gamestate.tree().current();
+        for child in node.children.iter().rev() {
+            nodes.push((child, reached));
+        }
+    }
+
+    let main_line_end = gamestate.tree().main_line_end(GameTree::ROOT);
+    gamestate.go_to(main_line_end)?;
+
+    // Games which ended by resignation end the same way here
+    if let Some((_, result)) = root.get_value("RE") {
+        let loser = match result.trim().split_once('+') {
+            Some(("B", "R" | "Resign")) => Some(IntersectionState::White),
+            Some(("W", "R" | "Resign")) => Some(IntersectionState::Black),
+            _ => None,
+        };
+
+        if let Some(loser) = loser.filter(|_| !gamestate.status.is_over()) {
+            gamestate = gamestate.apply_command(GameCommand::Resign(loser))?;
+        }
+    }
+
+    Ok(gamestate)
+}
+
+/// Applies the setup, player to move and move of a single node
+fn replay_node(
+    mut gamestate: GameState,
+    node: &SgfNode,
+    width: usize,
+    height: usize,
+) -> Result<GameState, GoError> {
+    for (identifier, state) in [
+        ("AB", IntersectionState::Black),
+        ("AW", IntersectionState::White),
+        ("AE", IntersectionState::Empty),
+    ] {
+        for property in node
+            .properties
+            .iter()
+            .filter(|p| p.identifier == identifier)
+        {
+            for value in property.values.iter() {
+                for (x, y) in parse_point_list(property, value, width, height)? {
+                    gamestate = gamestate.apply_command(GameCommand::Setup(x, y, state))?;
+                }
+            }
+        }
+    }
+
+    if let Some((property, value)) = node.get_value("PL") {
+        gamestate.to_move = match value.trim() {
+            "B" | "b" => IntersectionState::Black,
+            "W" | "w" => IntersectionState::White,
+            _ => return Err(property.invalid_value(value)),
+        };
+    }
+
+    for (identifier, player) in [
+        ("B", IntersectionState::Black),
+        ("W", IntersectionState::White),
+    ] {
+        let Some((property, value)) = node.get_value(identifier) else {
+            continue;
+        };
+
+        // An empty move is a pass, and so is tt on boards up to 19x19
+        let command = if value.is_empty() || (value == "tt" && width <= 19 && height <= 19) {
+            GameCommand::Pass
+        } else {
+            let (x, y) = from_sgf_point(value)
+                .filter(|&(x, y)| x < width && y < height)
+                .ok_or_else(|| property.invalid_value(value))?;
+            GameCommand::Set(x, y, player)
+        };
+
+        gamestate =
+            gamestate
+                .apply_command_as(player, command)
+                .map_err(|e| GoError::SgfIllegalMove {
+                    line: property.line,
+                    column: property.column,
+                    reason: e.to_string(),
+                })?;
+    }
+
+    Ok(gamestate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::IntersectionState::{Black, Empty, White};
+    use crate::errors::GoError;
+    use crate::gamecommand::GameCommand;
+    use crate::gamestate::{GameState, GameStatus};
+
+    #[test]
+    pub fn export() {
+        let mut gamestate = GameState::new();
+        gamestate.info.black_player = String::from("Alice");
+        gamestate.info.white_player = String::from("Bob [2k]");
+
+        let mut gamestate = gamestate
+            .apply_command(GameCommand::Setup(2, 2, Black))
+            .unwrap()
+            .apply_command(GameCommand::Setup(6, 6, White))
+            .unwrap();
+        gamestate.to_move = White;
+
+        let gamestate = gamestate
+            .apply_command(GameCommand::Set(4, 4, White))
+            .unwrap()
+            .apply_command(GameCommand::Pass)
+            .unwrap()
+            .apply_command(GameCommand::Set(2, 3, White))
+            .unwrap()
+            .apply_command(GameCommand::Setup(0, 0, Black))
+            .unwrap()
+            .apply_command(GameCommand::Setup(6, 6, Empty))
+            .unwrap()
+            .apply_command(GameCommand::Set(8, 8, Black))
+            .unwrap()
+            .apply_command(GameCommand::Resign(Black))
+            .unwrap();
+
+        assert_eq!(
+            super::to_sgf(&gamestate).unwrap(),
+            "(;GM[1]FF[4]CA[UTF-8]AP[gorst]SZ[9]KM[6.5]RU[Japanese]PB[Alice]PW[Bob [2k\\]]RE[W+R]AB[cc]AW[gg]PL[W]\n\
+             ;W[ee];B[];W[cd];AB[aa]AE[gg];B[ii])\n"
+        );
+    }
+
+    #[test]
+    pub fn export_empty_game() {
+        assert_eq!(
+            super::to_sgf(&GameState::new()).unwrap(),
+            "(;GM[1]FF[4]CA[UTF-8]AP[gorst]SZ[9]KM[6.5]RU[Japanese]PB[]PW[]\n)\n"
+        );
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let sgf = "(;GM[1]FF[4]CA[UTF-8]AP[gorst]SZ[9]KM[6.5]RU[Japanese]PB[Alice]PW[Bob [2k\\]]RE[W+R]AB[cc]AW[gg]PL[W]\n\
+                   ;W[ee];B[];W[cd];AB[aa]AE[gg];B[ii])\n";
+
+        let gamestate = super::from_sgf(sgf).unwrap();
+
+        assert_eq!(gamestate.info.white_player, "Bob [2k]");
+        assert_eq!(gamestate.status, GameStatus::Resigned(Black));
+        assert_eq!(super::to_sgf(&gamestate).unwrap(), sgf);
+    }
+
+    #[test]
+    pub fn variations_round_trip() {
+        let sgf = "(;GM[1]FF[4]CA[UTF-8]AP[gorst]SZ[9]KM[6.5]RU[Japanese]PB[]PW[]C[A problem]\n\
+                   ;B[ee];W[dd](;B[cc]C[Main line])(;B[]C[Pass \\] here];W[cc])(;AB[aa]AW[ba]))\n";
+
+        let gamestate = super::from_sgf(sgf).unwrap();
+
+        assert_eq!(
+            gamestate.current().board.get_intersection(2, 2),
+            Some(Black)
+        );
+        assert_eq!(gamestate.tree().current_node().comment, "Main line");
+        assert_eq!(super::to_sgf(&gamestate).unwrap(), sgf);
+    }
+
+    #[test]
+    pub fn import_recomputes_captures() {
+        // White is captured in the corner, then black plays twice in a row
+        let gamestate = super::from_sgf(
+            "(;SZ[5]KM[0.5]RU[Chinese]\n;B[ba];W[aa];B[ab]\n;B[cc](;W[dd])(;W[ee]))",
+        )
+        .unwrap();
+
+        let latest = gamestate.current();
+        assert_eq!(latest.board.size(), 5);
+        assert_eq!(latest.board.get_intersection(0, 0), Some(Empty));
+        assert_eq!(latest.black_prisoners, 1);
+        assert_eq!(gamestate.ruleset.komi, 0.5);
+
+        // The main line is followed
+        assert_eq!(latest.board.get_intersection(3, 3), Some(White));
+        assert_eq!(latest.board.get_intersection(4, 4), Some(Empty));
+        assert_eq!(gamestate.to_move, Black);
+    }
+
+    #[test]
+    pub fn import_point_lists() {
+        let gamestate = super::from_sgf("(;SZ[5]AB[aa:bc]AW[ee])").unwrap();
+        let board = &gamestate.current().board;
+
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)] {
+            assert_eq!(board.get_intersection(x, y), Some(Black));
+        }
+        assert_eq!(board.get_intersection(4, 4), Some(White));
+        assert_eq!(board.get_intersection(2, 0), Some(Empty));
+    }
+
+    #[test]
+    pub fn rectangular_board() {
+        let sgf = "(;GM[1]FF[4]CA[UTF-8]AP[gorst]SZ[7:9]KM[6.5]RU[Japanese]PB[]PW[]\n\
+                   ;B[gi];W[ag])\n";
+
+        let gamestate = super::from_sgf(sgf).unwrap();
+        let board = &gamestate.current().board;
+
+        assert_eq!((board.width(), board.height()), (7, 9));
+        assert_eq!(board.get_intersection(6, 8), Some(Black));
+        assert_eq!(super::to_sgf(&gamestate).unwrap(), sgf);
+
+        // Past the right edge, even though the board is 9 high
+        assert!(super::from_sgf("(;SZ[7:9];B[hb])").is_err());
+    }
+
+    #[test]
+    pub fn export_too_large() {
+        let gamestate = GameState::with_board(Board::new_rect(53, 53))
+            .apply_command(GameCommand::Set(52, 52, Black))
+            .unwrap();
+
+        assert_eq!(
+            super::to_sgf(&gamestate),
+            Err(GoError::SgfBoardTooLarge {
+                width: 53,
+                height: 53
+            })
+        );
+
+        // 52 still fits, with the last column written as Z
+        let gamestate = GameState::with_board(Board::new_rect(52, 9))
+            .apply_command(GameCommand::Set(51, 0, Black))
+            .unwrap();
+        assert!(super::to_sgf(&gamestate).unwrap().contains(";B[Za]"));
+    }
+
+    #[test]
+    pub fn import_errors() {
+        assert_eq!(
+            super::from_sgf("(;SZ[9]\n;B[ee];W[ee])"),
+            Err(GoError::SgfIllegalMove {
+                line: 2,
+                column: 8,
+                reason: GoError::IntersectionOccupied {
+                    x: 4,
+                    y: 4,
+                    height: 9
+                }
+                .to_string()
+            })
+        );
+        assert_eq!(
+            super::from_sgf("(;SZ[9]\n;B[zz])"),
+            Err(GoError::SgfInvalidValue {
+                property: String::from("B"),
+                value: String::from("zz"),
+                line: 2,
+                column: 2
+            })
+        );
+        assert_eq!(
+            super::from_sgf("(;SZ[9]\n  ;B[ee]x)"),
+            Err(GoError::SgfUnexpectedCharacter {
+                character: 'x',
+                line: 2,
+                column: 9
+            })
+        );
+        assert_eq!(
+            super::from_sgf("(;SZ[9];B[ee"),
+            Err(GoError::SgfUnexpectedEnd {
+                line: 1,
+                column: 13
+            })
+        );
+    }
+}
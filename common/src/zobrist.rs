@@ -0,0 +1,136 @@
+use crate::board::{Board, IntersectionState};
+
+/// Keys are derived from this, so hashes are the same every time the program runs
+const SEED: u64 = 0x676f_7273_745f_7a62;
+
+/// Mixed into a position hash when white is to move
+pub const WHITE_TO_MOVE: u64 = 0x8f3d_91c4_27b6_e05a;
+
+/// Scrambles a number into a well distributed one (splitmix64)
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns the key of a stone at an index of the board, counted row by row;
+///
+/// Empty intersections have no key.
+pub fn stone_key(index: usize, state: IntersectionState) -> u64 {
+    let color = match state {
+        IntersectionState::Empty => return 0,
+        IntersectionState::Black => 1,
+        IntersectionState::White => 2,
+    };
+
+    mix(SEED ^ (index as u64 * 3 + color))
+}
+
+/// Returns the key for the player to move, which is nothing for black
+pub fn side_to_move_key(player: IntersectionState) -> u64 {
+    match player {
+        IntersectionState::White => WHITE_TO_MOVE,
+        _ => 0,
+    }
+}
+
+/// Computes the hash of a board's stones from scratch.
+///
+/// Boards keep their hash up to date as stones are placed and removed, so this is mostly for checking it.
+pub fn hash_board(board: &Board) -> u64 {
+    let mut hash = 0;
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let state = board.get_intersection(x, y).unwrap_or_default();
+            hash ^= stone_key(y * board.width() + x, state);
+        }
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, IntersectionState::*};
+    use crate::gamecommand::GameCommand;
+    use crate::gamestate::GameState;
+    use crate::player::{Player, RandomPlayer};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    pub fn hash_follows_games() {
+        for seed in 0..4 {
+            let mut player = RandomPlayer::with_seed(seed);
+            let mut gamestate = GameState::with_board(Board::new(7));
+            let mut captures = 0;
+
+            while !gamestate.status.is_over() {
+                let command = player.genmove(&gamestate).unwrap();
+                gamestate = gamestate.apply_command(command).unwrap();
+
+                let boardstate = gamestate.current();
+                captures = boardstate.black_prisoners + boardstate.white_prisoners;
+
+                assert_eq!(
+                    boardstate.board.zobrist_hash(),
+                    super::hash_board(&boardstate.board)
+                );
+            }
+
+            assert!(captures > 0);
+
+            // Going back restores the hashes along with the boards
+            let gamestate = gamestate.apply_command(GameCommand::First).unwrap();
+            assert_eq!(gamestate.current().board.zobrist_hash(), 0);
+        }
+    }
+
+    #[test]
+    pub fn hash_follows_edits() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut board = Board::new(5);
+        let states = [Empty, Black, White];
+
+        for _ in 0..500 {
+            let (x, y) = (rng.gen_range(0..5), rng.gen_range(0..5));
+
+            if rng.gen_bool(0.1) {
+                board.remove_group(x, y).unwrap();
+            } else {
+                board
+                    .set_intersection(x, y, states[rng.gen_range(0..3)])
+                    .unwrap();
+            }
+
+            assert_eq!(board.zobrist_hash(), super::hash_board(&board));
+        }
+
+        let mut copy = Board::new(5);
+        copy.set_data(board.get_data());
+        assert_eq!(copy.zobrist_hash(), board.zobrist_hash());
+    }
+
+    #[test]
+    pub fn side_to_move() {
+        let gamestate = GameState::new()
+            .apply_command(GameCommand::Set(2, 2, Black))
+            .unwrap();
+        let boardstate = gamestate.current();
+
+        assert_eq!(
+            boardstate.position_hash(None),
+            boardstate.board.zobrist_hash()
+        );
+        assert_eq!(
+            boardstate.position_hash(Some(Black)),
+            boardstate.board.zobrist_hash()
+        );
+        assert_ne!(
+            boardstate.position_hash(Some(White)),
+            boardstate.position_hash(Some(Black))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+#!/bin/sh
+# A tiny GTP engine for testing the GTP controller offline.
+#
+# Usage: mock_engine.sh <mode> [replies...]
+#
+# Every command is accepted. genmove answers with the given replies in order, then passes.
+# In the "crash" mode genmove exits instead, and in the "hang" mode it doesn't answer.
+# In the "slow" mode the first genmove answers after a second, with an extra line.
+
+mode=$1
+shift
+replies="$*"
+
+while read -r first rest; do
+    id=""
+    command=$first
+
+    case $first in
+        [0-9]*)
+            id=$first
+            command=${rest%% *}
+            ;;
+    esac
+
+    case $command in
+        name)
+            printf '=%s mock\n\n' "$id"
+            ;;
+        genmove)
+            case $mode in
+                crash) exit 1 ;;
+                hang) sleep 5 ;;
+                slow) sleep 1 ;;
+            esac
+
+            reply=${replies%% *}
+            replies=${replies#"$reply"}
+            replies=${replies# }
+
+            if [ "$mode" = slow ]; then
+                mode=normal
+                printf '=%s %s\nlate\n\n' "$id" "${reply:-pass}"
+            else
+                printf '=%s %s\n\n' "$id" "${reply:-pass}"
+            fi
+            ;;
+        quit)
+            printf '=%s\n\n' "$id"
+            exit 0
+            ;;
+        *)
+            printf '=%s\n\n' "$id"
+            ;;
+    esac
+done